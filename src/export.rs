@@ -0,0 +1,139 @@
+use std::path::Path;
+
+use crate::history::*;
+use crate::music::*;
+use crate::stats::{format_day, SECONDS_DAY};
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ExportFormat {
+    Csv,
+    Json,
+}
+
+impl ExportFormat {
+    pub fn from_name(name: &str) -> Option<ExportFormat> {
+        match name {
+            "csv" => Some(Self::Csv),
+            "json" => Some(Self::Json),
+            _ => None,
+        }
+    }
+
+    pub fn get_extension(&self) -> &str {
+        match self {
+            Self::Csv => "csv",
+            Self::Json => "json",
+        }
+    }
+}
+
+//written note as C#4, the octave numbers are the ones of scientific pitch notation
+fn get_note_name(r: &NoteResult) -> String {
+    format_note(r.key.pitch, r.key.accidental, r.key.octave)
+}
+
+//written note in the chosen naming, without octave
+fn get_name(r: &NoteResult, naming: NoteNaming) -> String {
+    naming.get_name(r.key.pitch, r.key.accidental)
+}
+
+fn get_played(r: &NoteResult, separator: &str) -> String {
+    r.played
+        .iter()
+        .map(|p| p.0.to_string())
+        .collect::<Vec<String>>()
+        .join(separator)
+}
+
+fn escape_csv(s: &str) -> String {
+    format!("\"{}\"", s.replace('"', "\"\""))
+}
+
+fn escape_json(s: &str) -> String {
+    let mut e = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => e += "\\\"",
+            '\\' => e += "\\\\",
+            c if (c as u32) < 0x20 => e += &format!("\\u{:04x}", c as u32),
+            c => e.push(c),
+        }
+    }
+    e + "\""
+}
+
+//one line per written note
+fn to_csv(sessions: &[&Session], naming: NoteNaming) -> String {
+    let mut s = String::from(
+        "session_start,date,settings,clef,note,name,semitone,played_semitones,right,reaction_ms\n",
+    );
+    for session in sessions.iter() {
+        for r in session.results.iter() {
+            s += &format!(
+                "{},{},{},{:?},{},{},{},{},{},{}\n",
+                session.start,
+                format_day(session.start / SECONDS_DAY),
+                escape_csv(&session.settings),
+                r.key.clef,
+                escape_csv(&get_note_name(r)),
+                escape_csv(&get_name(r, naming)),
+                r.key
+                    .get_semitone()
+                    .map(|s| s.0.to_string())
+                    .unwrap_or_default(),
+                escape_csv(&get_played(r, " ")),
+                r.right,
+                r.reaction_ms.map(|ms| ms.to_string()).unwrap_or_default(),
+            );
+        }
+    }
+    s
+}
+
+//an array of sessions holding their results
+fn to_json(sessions: &[&Session], naming: NoteNaming) -> String {
+    let mut json_sessions = Vec::new();
+    for session in sessions.iter() {
+        let results: Vec<String> = session
+            .results
+            .iter()
+            .map(|r| {
+                format!(
+                    "{{\"clef\":\"{:?}\",\"note\":{},\"name\":{},\"semitone\":{},\"played_semitones\":[{}],\"right\":{},\"reaction_ms\":{}}}",
+                    r.key.clef,
+                    escape_json(&get_note_name(r)),
+                    escape_json(&get_name(r, naming)),
+                    r.key.get_semitone().map(|s| s.0.to_string()).unwrap_or("null".to_string()),
+                    get_played(r, ","),
+                    r.right,
+                    r.reaction_ms.map(|ms| ms.to_string()).unwrap_or("null".to_string()),
+                )
+            })
+            .collect();
+        json_sessions.push(format!(
+            "{{\"start\":{},\"date\":\"{}\",\"settings\":{},\"results\":[\n{}\n]}}",
+            session.start,
+            format_day(session.start / SECONDS_DAY),
+            escape_json(&session.settings),
+            results.join(",\n")
+        ));
+    }
+    format!("[\n{}\n]\n", json_sessions.join(",\n"))
+}
+
+pub fn export(sessions: &[&Session], format: ExportFormat, naming: NoteNaming) -> String {
+    match format {
+        ExportFormat::Csv => to_csv(sessions, naming),
+        ExportFormat::Json => to_json(sessions, naming),
+    }
+}
+
+pub fn export_to_file(
+    sessions: &[&Session],
+    format: ExportFormat,
+    naming: NoteNaming,
+    path: &Path,
+) -> Result<(), String> {
+    std::fs::write(path, export(sessions, format, naming))
+        .map_err(|e| format!("{}: {}", path.display(), e))
+}
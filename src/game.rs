@@ -1,502 +1,1254 @@
-use rand::distributions::Standard;
-use rand::rngs::ThreadRng;
-use rand::Rng;
-use std::collections::HashMap;
-
-use sdl2::pixels::Color;
-use sdl2::rect::Point;
-use sdl2::render::WindowCanvas;
+use std::time::{Duration, Instant};
 
+#[cfg(feature = "sdl")]
 use sdl2::gfx::primitives::DrawRenderer;
+#[cfg(feature = "sdl")]
+use sdl2::render::WindowCanvas;
 
+use crate::export::*;
+use crate::generator::*;
+use crate::graphics::{Color, Point};
+use crate::history::*;
+use crate::key_drill::KeySignatureDrill;
+use crate::keyboard::Keyboard;
+use crate::midi::{is_pedal_down, MidiDecoder, MidiMessage, Pedal};
 use crate::music::*;
+use crate::repetition::NoteKey;
+#[cfg(feature = "sdl")]
+use crate::review::Review;
+#[cfg(feature = "sdl")]
+use crate::stats::{draw_stats, get_reaction_stats};
+use crate::stave::*;
 
-#[derive(Debug, Clone, Copy, Hash)]
-struct Note {
-    pitch: Pitch,
-    accidental: Option<Accidental>,
-    octave: Octave,
-    color: Color,
-    draw_acci: bool,
+//how a held pedal acts on the released keys
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum PedalMode {
+    //a released key is released, pedals are only tracked
+    Keys,
+    //a key released under the sustain (or caught by the sostenuto) is released when the pedal goes up, like its sound
+    Sound,
 }
 
-impl Note {
-    fn new(pitch: Pitch, accidental: Option<Accidental>, octave: Octave) -> Note {
-        Note {
-            pitch,
-            accidental,
-            octave,
-            color: Color::BLACK,
-            draw_acci: true,
-        }
-    }
-    fn new_random(rng: &mut ThreadRng, c: Clef) -> Note {
-        match c {
-            Clef::Sol => {
-                //A3 -> G5
-                Note::new(
-                    rng.sample(Standard),
-                    rng.sample(Standard),
-                    Octave(rng.gen_range(3..=4)),
-                )
-            }
-            Clef::Fa => {
-                //A1 -> G3
-                Note::new(
-                    rng.sample(Standard),
-                    rng.sample(Standard),
-                    Octave(rng.gen_range(1..=2)),
-                )
-            }
-        }
-    }
-    fn to_semitone(&self) -> Semitone {
-        //A-1 on my piano is 21
-        //C0 = 24
-        let mut v = 24;
-        v += self.octave.0 * 12;
-        v += self.pitch.get_semitone_offset() as i32;
-        if let Some(a) = self.accidental {
-            v += a.get_semitone_offset() as i32;
-        }
-        Semitone(v as u8)
-    }
+#[derive(Debug, Default)]
+struct Pedals {
+    sustain: bool,
+    sostenuto: bool,
+    soft: bool,
+    //keys down when the sostenuto was pressed
+    sostenuto_semitones: Vec<Semitone>,
+    //the sustain went up since the searched event is searched, for PedalMark::Change
+    lifted: bool,
+    //the searched event was played but its mark is not done yet
+    waiting: bool,
 }
 
-//Note is same independently of its color
-impl PartialEq for Note {
-    fn eq(&self, other: &Self) -> bool {
-        self.to_semitone() == other.to_semitone()
+impl Pedals {
+    fn is_holding(&self, semitone: &Semitone) -> bool {
+        self.sustain || (self.sostenuto && self.sostenuto_semitones.contains(semitone))
     }
-}
-impl Eq for Note {}
 
-struct Measure {
-    notes: Vec<Note>,
-}
-
-impl Measure {
-    fn new(mut notes: Vec<Note>, key_sign: KeySignature) -> Measure {
-        //we need to treat notes to have coerent accidentals
-        let mut previous_accidentals: HashMap<Pitch, Accidental> = HashMap::new();
-
-        for n in notes.iter_mut() {
-            if let Some(acci) = previous_accidentals.get(&n.pitch) {
-                if n.accidental.is_some() {
-                    if n.accidental.unwrap() == *acci {
-                        n.draw_acci = false;
-                    } else {
-                        previous_accidentals.insert(n.pitch, n.accidental.unwrap());
-                    }
-                }
-                //we convert to what was previous
-                else {
-                    n.accidental = Some(*acci);
-                    //but we dont draw accidental
-                    n.draw_acci = false;
-                }
-            } else {
-                //first time we encounter this accidental
-                if n.accidental.is_some() {
-                    if key_sign.is_pitch_inside(n.pitch) {
-                        if key_sign.accidental_match(n.accidental.unwrap()) {
-                            n.draw_acci = false;
-                        } else {
-                            previous_accidentals.insert(n.pitch, n.accidental.unwrap());
-                        }
-                    } else {
-                        previous_accidentals.insert(n.pitch, n.accidental.unwrap());
-                        if n.accidental == Some(Accidental::Natural) {
-                            n.draw_acci = false;
-                        }
-                    }
-                } else if key_sign.is_pitch_inside(n.pitch) {
-                    n.accidental = Some(key_sign.get_accidental());
-                    n.draw_acci = false;
-                }
-            }
+    fn is_mark_done(&self, mark: Option<PedalMark>) -> bool {
+        match mark {
+            None => true,
+            Some(PedalMark::Press) => self.sustain,
+            Some(PedalMark::Release) => !self.sustain,
+            Some(PedalMark::Change) => self.sustain && self.lifted,
         }
-
-        Measure { notes }
     }
 }
 
-fn get_factor_gap_octave(o: &Octave, clef: &Clef) -> i32 {
-    match clef {
-        Clef::Sol => (4 - o.0) * 7,
-        Clef::Fa => (2 - o.0) * 7,
-    }
+#[derive(Debug, Clone, Copy)]
+struct HeldSemitone {
+    semitone: Semitone,
+    pressed_at: Instant,
+    //the key is released but a pedal still holds it
+    sustained: bool,
 }
 
-fn get_factor_gap_pitch(p: &Pitch, clef: &Clef) -> i32 {
-    let mut r = match p {
-        Pitch::A => -5,
-        Pitch::B => -6,
-        Pitch::C => 0,
-        Pitch::D => -1,
-        Pitch::E => -2,
-        Pitch::F => -3,
-        Pitch::G => -4,
-    };
-    if *clef == Clef::Fa {
-        r += 2;
-    }
-    r + 3
+//keys pressed within this time are played together
+const CHORD_WINDOW: Duration = Duration::from_millis(250);
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Timing {
+    OnTime,
+    Early,
+    Late,
+    Missed,
 }
 
-struct Stave {
-    x_pos: i32,
-    size: Point,
-    //height between two consecutives notes
-    //= radius of notes
-    //gap*2 = gap between two lines
-    gap: i32,
-    clef: Clef,
-    key_signature: KeySignature,
-    measures: Vec<Measure>,
+#[derive(Debug, Clone, Copy)]
+pub struct TimingResult {
+    pub timing: Timing,
+    //press time minus expected time, None when missed
+    pub error_ms: Option<i32>,
 }
 
-impl Stave {
-    #[allow(dead_code)]
-    pub fn new(x_pos: i32, size: Point, clef: Clef, key_signature: KeySignature) -> Stave {
-        let gap = size.y / 10;
-
-        Stave {
-            x_pos,
-            size,
-            gap,
-            measures: Vec::new(),
-            key_signature,
-            clef,
-        }
-    }
-
-    pub fn new_random(x_pos: i32, size: Point, clef: Clef, key_signature: KeySignature) -> Stave {
-        let mut s = Stave::new(x_pos, size, clef, key_signature);
-        let mut rng = rand::thread_rng();
-        s.add_measure(Measure::new(
-            vec![
-                Note::new_random(&mut rng, clef),
-                Note::new_random(&mut rng, clef),
-                Note::new_random(&mut rng, clef),
-                Note::new_random(&mut rng, clef),
-            ],
-            key_signature,
-        ));
-        s.add_measure(Measure::new(
-            vec![
-                Note::new_random(&mut rng, clef),
-                Note::new_random(&mut rng, clef),
-                Note::new_random(&mut rng, clef),
-                Note::new_random(&mut rng, clef),
-            ],
-            key_signature,
-        ));
-        s.add_measure(Measure::new(
-            vec![
-                Note::new_random(&mut rng, clef),
-                Note::new_random(&mut rng, clef),
-                Note::new_random(&mut rng, clef),
-                Note::new_random(&mut rng, clef),
-            ],
-            key_signature,
-        ));
-        s
-    }
-
-    pub fn add_measure(&mut self, m: Measure) {
-        self.measures.push(m);
-    }
-
-    pub fn draw(&self, y_pos: i32, canvas: &WindowCanvas) {
-        let pos = Point::new(self.x_pos, y_pos);
-        //draw lines
-        for i in 0..5 {
-            canvas
-                .thick_line(
-                    pos.x as i16,
-                    (pos.y + (self.gap * 2 * i) as i32) as i16,
-                    (pos.x + self.size.x as i32) as i16,
-                    (pos.y + self.gap * 2 * i) as i16,
-                    2,
-                    Color::BLACK,
-                )
-                .unwrap();
-        }
-
-        //draw clef
-        let pos_clef = match self.clef {
-            Clef::Sol => pos.y + self.size.y / 2,
-            Clef::Fa => pos.y + self.size.y / 2 - 20,
+//played events are on time inside this error
+const ON_TIME_MS: i32 = 80;
+//farthest a press can be from its event, less for short events
+const TIMING_WINDOW_MS: i32 = 250;
+
+impl TimingResult {
+    fn new(error_ms: i32) -> TimingResult {
+        let timing = match error_ms {
+            e if e < -ON_TIME_MS => Timing::Early,
+            e if e > ON_TIME_MS => Timing::Late,
+            _ => Timing::OnTime,
         };
-        canvas
-            .string(
-                pos.x as i16 - 23,
-                pos_clef as i16,
-                &self.clef.to_string(),
-                Color::BLACK,
-            )
-            .unwrap();
-
-        //draw key_signature
-        let small_gap_x = self.size.x / 60;
-        let s;
-        let order;
-        match self.key_signature.0 {
-            KeySignatureAccidental::Sharp => {
-                s = '#';
-                order = ORDER_SIGNATURE_SHARP;
-            }
-            KeySignatureAccidental::Flat => {
-                s = 'b';
-                order = ORDER_SIGNATURE_FLAT;
-            }
+        TimingResult {
+            timing,
+            error_ms: Some(error_ms),
         }
+    }
 
-        for i in 0..self.key_signature.get_number() {
-            let y = pos.y + get_factor_gap_pitch(&order[i as usize], &self.clef) * self.gap + 1
-                - self.gap;
-            let x = pos.x + small_gap_x * i as i32;
-            canvas
-                .character(x as i16, y as i16, s, Color::BLACK)
-                .unwrap();
-        }
-
-        let mut current_x = pos.x + small_gap_x * 8;
-        let gap_x = self.size.x / 18;
-        //draw measures
-        for m in self.measures.iter() {
-            //draw measures separating lines
-            canvas
-                .thick_line(
-                    current_x as i16,
-                    pos.y as i16,
-                    current_x as i16,
-                    (pos.y + self.gap * 8) as i16,
-                    2,
-                    Color::BLACK,
-                )
-                .unwrap();
-            current_x += gap_x;
-
-
-            //draw notes
-            for n in m.notes.iter() {
-                let nb_factor_gap = get_factor_gap_pitch(&n.pitch, &self.clef)
-                    + get_factor_gap_octave(&n.octave, &self.clef);
-                let y = pos.y + nb_factor_gap * self.gap;
-                let x = current_x;
-                canvas
-                    .filled_circle(x as i16, y as i16, self.gap as i16, n.color)
-                    .unwrap();
+    fn missed() -> TimingResult {
+        TimingResult {
+            timing: Timing::Missed,
+            error_ms: None,
+        }
+    }
+}
 
-                //draw accidental
-                if n.draw_acci && n.accidental.is_some() {
-                    match n.accidental.unwrap() {
-                        Accidental::Sharp => canvas
-                            .character((x - small_gap_x - 2) as i16, (y - 4) as i16, '#', n.color)
-                            .unwrap(),
-                        Accidental::Flat => canvas
-                            .character((x - small_gap_x - 2) as i16, (y - 4) as i16, 'b', n.color)
-                            .unwrap(),
-                        Accidental::Natural => canvas
-                            .character((x - small_gap_x - 2) as i16, (y - 4) as i16, 'n', n.color)
-                            .unwrap(),
-                    }
-                }
+//a cursor goes through the staves at the tempo, events are played on time
+struct Metronome {
+    bpm: u32,
+    //first beat of the first stave
+    stave_start: Instant,
+}
 
-                //draw help lines
-                let help_line_width = (self.gap as f32 * 1.5) as i32;
-                if nb_factor_gap <= -2 {
-                    for i_y in (2..=-nb_factor_gap).step_by(2) {
-                        let y = pos.y + i_y * -self.gap;
-                        canvas
-                            .thick_line(
-                                (x - help_line_width) as i16,
-                                y as i16,
-                                (x + help_line_width) as i16,
-                                y as i16,
-                                2,
-                                n.color,
-                            )
-                            .unwrap();
-                    }
-                } else if nb_factor_gap >= 10 {
-                    for i_y in (10..=nb_factor_gap).step_by(2) {
-                        let y = pos.y + i_y * self.gap;
-                        canvas
-                            .thick_line(
-                                (x - help_line_width) as i16,
-                                y as i16,
-                                (x + help_line_width) as i16,
-                                y as i16,
-                                2,
-                                n.color,
-                            )
-                            .unwrap();
-                    }
-                }
+impl Metronome {
+    //bpm counts quarter notes
+    fn get_tick_duration(&self) -> Duration {
+        Duration::from_secs_f64(60. / self.bpm as f64 / NoteValue::Quarter.get_ticks() as f64)
+    }
 
-                current_x += gap_x;
-            }
+    //from the start of the first stave, negative during the count-in
+    fn get_elapsed_ms(&self, t: Instant) -> i64 {
+        match t.checked_duration_since(self.stave_start) {
+            Some(d) => d.as_millis() as i64,
+            None => -(self.stave_start.duration_since(t).as_millis() as i64),
         }
     }
 }
 
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Screen {
+    Staves,
+    Stats,
+    Review,
+}
+
 pub struct Game {
     size_stave: Point,
     x_pos_stave: i32,
+    //top of the piano keyboard under the staves
+    y_pos_keyboard: i32,
+    //key pressed with the mouse on the keyboard
+    clicked_semitone: Option<Semitone>,
     staves: Vec<Stave>,
-    current_measure_note: (usize, usize),
-    pressed_semitone: Option<Semitone>,
-    score: (u32, u32)
+    current_measure_event: (usize, usize),
+    held_semitones: Vec<HeldSemitone>,
+    //first press of the chord being played
+    chord_start: Option<Instant>,
+    //keys of the last wrong try which are still down
+    wrong_semitones: Vec<Semitone>,
+    //when the searched event became the searched one, without the time on other screens
+    searched_at: Instant,
+    //when the staves were left for the statistics or the review
+    left_staves_at: Option<Instant>,
+    //the searched event was already missed once, it is not learned even if it is played right after
+    searched_missed: bool,
+    //the right keys of the searched event were all pressed, the event can still wait for the pedal
+    played_at: Option<Instant>,
+    //notes of the searched event already named in the names quiz
+    named: Vec<usize>,
+    //last name given in the names quiz and whether it was right
+    last_named: Option<(String, bool)>,
+    naming: NoteNaming,
+    score: (u32, u32),
+    midi_decoder: MidiDecoder,
+    generator: StaveGenerator,
+    pedal_mode: PedalMode,
+    pedals: Pedals,
+    //quarter notes per minute in timed mode
+    bpm: u32,
+    //timed mode, None waits for the right notes
+    metronome: Option<Metronome>,
+    timing_results: Vec<TimingResult>,
+    history: History,
+    //results since the last restart, added to the history at the next one
+    session: Session,
+    screen: Screen,
+    //made when the review screen is opened
+    #[cfg(feature = "sdl")]
+    review: Option<Review>,
+    //shown instead of the staves in the key signatures exercise
+    key_drill: Option<KeySignatureDrill>,
+    //result of the last export from the game
+    export_message: Option<String>,
 }
 
 impl Game {
-    pub fn new(screen_width: u32) -> Game {
+    //the history is loaded and saved by the caller, History::new keeps it in memory
+    pub fn new(screen_width: u32, screen_height: u32, history: History) -> Game {
         let width = (screen_width as f32 - (screen_width as f32 * 0.1)) as i32;
         let height = 50;
         let size_stave = Point::new(width, height);
         let x_pos_stave = ((screen_width as f32 - width as f32) / 2. as f32) as i32;
 
-        let mut staves = Vec::new();
-        for _ in 0..4 {
-            staves.push(Stave::new_random(
-                x_pos_stave,
-                size_stave,
-                rand::random(),
-                rand::random(),
+        let mut g = Game {
+            size_stave,
+            x_pos_stave,
+            y_pos_keyboard: screen_height as i32 - 110,
+            clicked_semitone: None,
+            staves: Vec::new(),
+            current_measure_event: (0, 0),
+            held_semitones: Vec::new(),
+            chord_start: None,
+            wrong_semitones: Vec::new(),
+            searched_at: Instant::now(),
+            left_staves_at: None,
+            searched_missed: false,
+            played_at: None,
+            named: Vec::new(),
+            last_named: None,
+            naming: NoteNaming::English,
+            score: (0,0),
+            midi_decoder: MidiDecoder::new(),
+            generator: StaveGenerator::new(),
+            pedal_mode: PedalMode::Keys,
+            pedals: Pedals::default(),
+            bpm: 60,
+            metronome: None,
+            timing_results: Vec::new(),
+            history,
+            session: Session::new(String::new()),
+            screen: Screen::Staves,
+            #[cfg(feature = "sdl")]
+            review: None,
+            key_drill: None,
+            export_message: None,
+        };
+        g.restart();
+        g
+    }
+
+    //new staves for the current exercise
+    fn restart(&mut self) {
+        self.save_session();
+        self.session = Session::new(self.get_settings());
+
+        self.staves.clear();
+        for _ in 0..self.generator.get_nb_staves() {
+            self.staves
+                .push(self.generator.new_stave(self.x_pos_stave, self.size_stave));
+        }
+
+        self.key_drill = match self.generator.exercise {
+            Exercise::KeySignatures => Some(KeySignatureDrill::new(
+                &self.generator,
+                self.x_pos_stave,
+                self.size_stave,
+            )),
+            _ => None,
+        };
+
+        self.current_measure_event = (0, 0);
+        self.chord_start = None;
+        self.set_searched_event();
+
+        //one measure to count in
+        let count_in = self.staves[0].time_signature.get_measure_ticks();
+        if let Some(m) = self.metronome.as_mut() {
+            m.stave_start = Instant::now() + m.get_tick_duration() * count_in;
+        }
+    }
+
+    //the results of the session are written in the history
+    pub fn save_session(&mut self) {
+        let settings = self.get_settings();
+        let session = std::mem::replace(&mut self.session, Session::new(settings));
+        if let Err(e) = self.history.add_session(session) {
+            println!("history not saved: {}", e);
+        }
+    }
+
+    fn get_settings(&self) -> String {
+        let rhythm = if self.generator.rhythm { "Rhythm" } else { "No rhythm" };
+        let timed = match &self.metronome {
+            Some(m) => format!("Timed {} bpm", m.bpm),
+            None => "Untimed".to_string(),
+        };
+        format!(
+            "{} / {} / {} / {}",
+            self.generator.exercise, self.generator.clef_policy, rhythm, timed
+        )
+    }
+
+    //the staves are shown to the player from now, the searched event is timed from here
+    pub fn start(&mut self) {
+        self.searched_at = Instant::now();
+    }
+
+    //the time on the other screens does not count in the reaction time, the metronome waits too
+    fn set_screen(&mut self, screen: Screen) {
+        match (self.screen, screen) {
+            (Screen::Staves, Screen::Stats | Screen::Review) => {
+                self.left_staves_at = Some(Instant::now())
+            }
+            (_, Screen::Staves) => {
+                if let Some(t) = self.left_staves_at.take() {
+                    let away = t.elapsed();
+                    self.searched_at += away;
+                    if let Some(m) = self.metronome.as_mut() {
+                        m.stave_start += away;
+                    }
+                }
+            }
+            _ => {}
+        }
+        self.screen = screen;
+    }
+
+    pub fn toggle_stats(&mut self) {
+        self.set_screen(match self.screen {
+            Screen::Stats => Screen::Staves,
+            _ => Screen::Stats,
+        });
+    }
+
+    pub fn toggle_review(&mut self) {
+        if self.screen == Screen::Review {
+            self.set_screen(Screen::Staves);
+            return;
+        }
+        #[cfg(feature = "sdl")]
+        {
+            self.review = Some(Review::new(
+                &self.get_sessions(),
+                &self.generator,
+                self.x_pos_stave,
+                self.size_stave,
             ));
         }
+        self.set_screen(Screen::Review);
+    }
+
+    //every session in CSV and JSON files next to the history
+    pub fn export(&mut self) {
+        let dir = match get_data_dir() {
+            Some(d) => d,
+            None => {
+                self.export_message = Some("no data directory".to_string());
+                return;
+            }
+        };
+        let sessions = self.get_sessions();
+        let mut result = std::fs::create_dir_all(&dir).map_err(|e| e.to_string());
+        for format in [ExportFormat::Csv, ExportFormat::Json] {
+            let path = dir.join(format!("export.{}", format.get_extension()));
+            result = result.and_then(|_| export_to_file(&sessions, format, self.naming, &path));
+        }
+        self.export_message = Some(match result {
+            Ok(_) => {
+                println!("exported to {}", dir.display());
+                "exported".to_string()
+            }
+            Err(e) => {
+                println!("export failed: {}", e);
+                "export failed".to_string()
+            }
+        });
+    }
 
-        let current_measure_note = (0, 0);
-        staves
+    //keys of the notes the staves can hold
+    fn get_keyboard(&self) -> Keyboard {
+        let (low, high) = self.generator.get_semitone_range();
+        Keyboard::new(
+            Point::new(self.x_pos_stave, self.y_pos_keyboard),
+            Point::new(self.size_stave.x, 100),
+            low,
+            high,
+        )
+    }
+
+    //a click on the keyboard presses the key until the button is released
+    pub fn click(&mut self, x: i32, y: i32) {
+        if self.screen != Screen::Staves {
+            return;
+        }
+        if let Some(s) = self.get_keyboard().get_semitone_at(x, y) {
+            self.clicked_semitone = Some(s);
+            self.pressed_semitone(&s);
+        }
+    }
+
+    pub fn unclick(&mut self) {
+        if let Some(s) = self.clicked_semitone.take() {
+            self.released_semitone(&s);
+        }
+    }
+
+    //the saved sessions and the current one
+    fn get_sessions(&self) -> Vec<&Session> {
+        let mut sessions: Vec<&Session> = self.history.sessions.iter().collect();
+        sessions.push(&self.session);
+        sessions
+    }
+
+    pub fn toggle_timed(&mut self) {
+        self.metronome = match self.metronome {
+            Some(_) => None,
+            None => Some(Metronome {
+                bpm: self.bpm,
+                stave_start: Instant::now(),
+            }),
+        };
+        self.restart();
+    }
+
+    pub fn set_bpm(&mut self, bpm: u32) {
+        self.bpm = bpm.clamp(20, 240);
+        if let Some(m) = self.metronome.as_mut() {
+            m.bpm = self.bpm;
+            self.restart();
+        }
+    }
+
+    pub fn get_bpm(&self) -> u32 {
+        self.bpm
+    }
+
+    pub fn next_exercise(&mut self) {
+        self.generator.exercise = self.generator.exercise.next();
+        self.restart();
+    }
+
+    pub fn toggle_rhythm(&mut self) {
+        self.generator.rhythm = !self.generator.rhythm;
+        self.restart();
+    }
+
+    pub fn toggle_adaptive(&mut self) {
+        self.generator.adaptive = !self.generator.adaptive;
+        self.restart();
+    }
+
+    pub fn next_clef_policy(&mut self) {
+        self.set_clef_policy(self.generator.clef_policy.next());
+    }
+
+    pub fn set_clef_policy(&mut self, clef_policy: ClefPolicy) {
+        self.generator.clef_policy = clef_policy;
+        self.restart();
+    }
+
+    pub fn set_range(&mut self, clef: Clef, range: NoteRange) {
+        self.generator.set_range(clef, range);
+        self.restart();
+    }
+
+    pub fn set_ledger_lines(&mut self, ledger_lines: u8) {
+        self.generator.ledger_lines = ledger_lines.clamp(1, 5);
+        self.restart();
+    }
+
+    pub fn set_pitch_weights(&mut self, pitch_weights: PitchWeights) {
+        self.generator.pitch_weights = pitch_weights;
+        self.restart();
+    }
+
+    pub fn set_accidental_weights(&mut self, accidental_weights: AccidentalWeights) {
+        self.generator.accidental_weights = accidental_weights;
+        self.restart();
+    }
+
+    pub fn set_key_signature_weights(&mut self, key_signature_weights: KeySignatureWeights) {
+        self.generator.key_signature_weights = key_signature_weights;
+        self.restart();
+    }
+
+    pub fn set_key_signature_policy(&mut self, key_signature_policy: KeySignaturePolicy) {
+        self.generator.key_signature_policy = key_signature_policy;
+        self.restart();
+    }
+
+    pub fn set_pedal_mode(&mut self, pedal_mode: PedalMode) {
+        self.pedal_mode = pedal_mode;
+    }
+
+    //channel from 0 to 15, None to listen every channel
+    pub fn set_midi_channel(&mut self, channel: Option<u8>) {
+        self.midi_decoder.set_channel(channel);
+    }
+
+    pub fn parse_midi_message(&mut self, message: &[u8]) {
+        for m in self.midi_decoder.decode(message) {
+            match m {
+                MidiMessage::NoteOn { key, .. } => self.pressed_semitone(&Semitone(key)),
+                MidiMessage::NoteOff { key, .. } => self.released_semitone(&Semitone(key)),
+                MidiMessage::ControlChange {
+                    controller, value, ..
+                } => self.control_change(controller, value),
+                //not used by the game, aftertouch can stream many messages a second
+                MidiMessage::PitchBend { .. }
+                | MidiMessage::PolyAftertouch { .. }
+                | MidiMessage::ChannelAftertouch { .. }
+                | MidiMessage::ProgramChange { .. } => {}
+            }
+        }
+    }
+
+    //the mod wheel and the other controllers are not used, they can stream many messages a second
+    fn control_change(&mut self, controller: u8, value: u8) {
+        if let Some(p) = Pedal::from_controller(controller) {
+            self.pedal_changed(p, is_pedal_down(value));
+        }
+    }
+
+    fn pedal_changed(&mut self, pedal: Pedal, down: bool) {
+        match pedal {
+            Pedal::Sustain => {
+                if self.pedals.sustain && !down {
+                    self.pedals.lifted = true;
+                }
+                self.pedals.sustain = down;
+            }
+            Pedal::Sostenuto => {
+                self.pedals.sostenuto = down;
+                self.pedals.sostenuto_semitones.clear();
+                if down {
+                    for h in self.held_semitones.iter() {
+                        self.pedals.sostenuto_semitones.push(h.semitone);
+                    }
+                }
+            }
+            Pedal::Soft => self.pedals.soft = down,
+        }
+
+        //the keys that are not held anymore are released now
+        let released: Vec<Semitone> = self
+            .held_semitones
+            .iter()
+            .filter(|h| h.sustained && !self.pedals.is_holding(&h.semitone))
+            .map(|h| h.semitone)
+            .collect();
+        for s in released.iter() {
+            self.release_semitone(s);
+        }
+
+        //the searched event was played and only waited for the pedal
+        let pedal = self.get_searched_event_mut().pedal;
+        if self.pedals.waiting && self.pedals.is_mark_done(pedal) {
+            self.score.0 += 1;
+            self.validate_searched_event();
+        }
+    }
+
+    fn get_searched_event_mut(&mut self) -> &mut MeasureEvent {
+        self.staves
             .get_mut(0)
             .unwrap()
             .measures
-            .get_mut(current_measure_note.0)
+            .get_mut(self.current_measure_event.0)
             .unwrap()
-            .notes
-            .get_mut(current_measure_note.1)
+            .events
+            .get_mut(self.current_measure_event.1)
             .unwrap()
-            .color = Color::GRAY;
+    }
 
-        Game {
-            size_stave,
-            x_pos_stave,
-            staves,
-            current_measure_note,
-            pressed_semitone: None,
-            score: (0,0),
+    //keys pressed since the start of the chord being played
+    fn get_played_semitones(&self, chord_start: Instant) -> Vec<Semitone> {
+        self.held_semitones
+            .iter()
+            .filter(|h| h.pressed_at >= chord_start)
+            .map(|h| h.semitone)
+            .collect()
+    }
+
+    //ms from the time the searched event should be played to t, None without metronome
+    fn get_timing_error(&self, t: Instant) -> Option<i32> {
+        let m = self.metronome.as_ref()?;
+        let ticks = self.staves[0]
+            .get_ticks_before(self.current_measure_event.0, self.current_measure_event.1);
+        let onset_ms = (m.get_tick_duration() * ticks).as_millis() as i64;
+        Some((m.get_elapsed_ms(t) - onset_ms) as i32)
+    }
+
+    //how far from its time the searched event can be played
+    fn get_timing_window_ms(&mut self) -> i32 {
+        let length = self.get_searched_event_mut().length;
+        match self.metronome.as_ref() {
+            Some(m) => {
+                let half_length = (m.get_tick_duration() * length.get_ticks()).as_millis() / 2;
+                TIMING_WINDOW_MS.min(half_length as i32)
+            }
+            None => TIMING_WINDOW_MS,
         }
     }
 
-    pub fn parse_midi_message(&mut self, message: &[u8]) {
-        // println!("{:?}", message);
-        let semitone = Semitone(message[1]);
-        //0 is key released
-        if message[2] == 0 {
-            self.released_semitone(&semitone);
+    //None when the event is missed
+    fn add_timing_result(&mut self, error_ms: Option<i32>) {
+        self.timing_results.push(match error_ms {
+            Some(e) => TimingResult::new(e),
+            None => TimingResult::missed(),
+        });
+    }
+
+    //with the metronome the music goes on without the searched event
+    fn pass_searched_event(&mut self) {
+        self.add_timing_result(None);
+        self.next_event();
+        self.set_searched_event();
+    }
+
+    //called each frame
+    pub fn update(&mut self) {
+        //the key signatures drill is not timed
+        if self.screen != Screen::Staves || self.key_drill.is_some() {
+            return;
+        }
+        if let Some(chord_start) = self.chord_start {
+            if chord_start.elapsed() > CHORD_WINDOW {
+                //the chord was not completed in time
+                self.chord_start = None;
+                self.wrong_semitones = self.get_played_semitones(chord_start);
+                self.miss_searched_event(self.wrong_semitones.clone());
+                self.score.1 += 1;
+                if self.metronome.is_some() {
+                    self.pass_searched_event();
+                }
+            }
+        }
+
+        //the searched event was not played in time
+        if let Some(error) = self.get_timing_error(Instant::now()) {
+            if self.chord_start.is_none() && error > self.get_timing_window_ms() {
+                self.miss_searched_event(Vec::new());
+                self.score.1 += 1;
+                self.pass_searched_event();
+            }
+        }
+    }
+
+    pub fn pressed_semitone(&mut self, pressed_semitone: &Semitone) {
+        //the notes are only played on the staves
+        if self.screen != Screen::Staves {
+            return;
+        }
+        let now = Instant::now();
+        self.held_semitones
+            .retain(|h| h.semitone != *pressed_semitone);
+        self.held_semitones.push(HeldSemitone {
+            semitone: *pressed_semitone,
+            pressed_at: now,
+            sustained: false,
+        });
+        //the keys are only shown, the notes or intervals are named
+        if matches!(
+            self.generator.exercise,
+            Exercise::Names | Exercise::IntervalNames
+        ) {
+            return;
+        }
+        if self.key_drill.is_some() {
+            return self.answer_key_drill(|d, g| d.answer_semitone(g, *pressed_semitone));
+        }
+        self.pedals.waiting = false;
+
+        let chord_start = match self.chord_start {
+            Some(start) if now.duration_since(start) <= CHORD_WINDOW => start,
+            _ => now,
+        };
+        self.chord_start = Some(chord_start);
+        let played_semitones = self.get_played_semitones(chord_start);
+
+        //with the metronome, a press long before the searched event is not for it
+        let timing_error = self.get_timing_error(chord_start);
+        if let Some(error) = timing_error {
+            if error < -self.get_timing_window_ms() {
+                self.chord_start = None;
+                self.score.1 += 1;
+                return;
+            }
+        }
+
+        let searched_event = self.get_searched_event_mut();
+        let semitones_searched_event = searched_event.get_semitones();
+        let pedal = searched_event.pedal;
+
+        if played_semitones
+            .iter()
+            .any(|s| !semitones_searched_event.contains(s))
+        {
+            self.miss_searched_event(played_semitones.clone());
+            self.wrong_semitones = played_semitones;
+            if self.metronome.is_some() {
+                self.pass_searched_event();
+            }
+        } else if played_semitones.len() == semitones_searched_event.len() {
+            self.played_at = Some(now);
+            if self.pedals.is_mark_done(pedal) {
+                self.score.0 += 1;
+                if let Some(error) = timing_error {
+                    self.add_timing_result(Some(error));
+                }
+                self.validate_searched_event();
+            } else if self.metronome.is_some() {
+                //no time to wait for the pedal
+                self.miss_searched_event(played_semitones);
+                self.pass_searched_event();
+            } else {
+                //right notes, waiting for the pedal
+                self.get_searched_event_mut().set_color(Color::BLUE);
+                self.pedals.waiting = true;
+            }
         } else {
-            self.pressed_semitone(&semitone);
+            //waiting for the other notes of the chord
+            return;
         }
+        self.chord_start = None;
+        self.score.1 += 1;
     }
 
-    fn pressed_semitone(&mut self, pressed_semitone: &Semitone) {
-        println!("pressed_semitone: {:?}", pressed_semitone);
-        self.pressed_semitone = Some(*pressed_semitone);
+    pub fn get_naming(&self) -> NoteNaming {
+        self.naming
+    }
 
-        let searched_note = self
-            .staves
-            .get_mut(0)
-            .unwrap()
-            .measures
-            .get_mut(self.current_measure_note.0)
-            .unwrap()
+    pub fn set_naming(&mut self, naming: NoteNaming) {
+        self.naming = naming;
+    }
+
+    pub fn next_naming(&mut self) {
+        self.naming = self.naming.next();
+        self.last_named = None;
+    }
+
+    //the letter or number keys answer the quiz instead of the piano keys
+    pub fn is_quiz(&self) -> bool {
+        self.screen == Screen::Staves
+            && matches!(
+                self.generator.exercise,
+                Exercise::Names | Exercise::KeySignatures | Exercise::IntervalNames
+            )
+    }
+
+    //right answers and answers
+    pub fn get_score(&self) -> (u32, u32) {
+        self.score
+    }
+
+    //early, late and missed events of the timed mode
+    pub fn get_timing_results(&self) -> &[TimingResult] {
+        &self.timing_results
+    }
+
+    //notes of the event to play or name
+    pub fn get_searched_notes(&self) -> Vec<Note> {
+        let (i_measure, i_event) = self.current_measure_event;
+        self.staves[0].measures[i_measure].events[i_event]
             .notes
-            .get_mut(self.current_measure_note.1)
-            .unwrap();
-        let semitone_searched_note = searched_note.to_semitone();
-        println!("searched_note: {:?}", searched_note);
-        println!("semitone_searched_note: {:?}", semitone_searched_note);
+            .clone()
+    }
 
-        if &semitone_searched_note == pressed_semitone {
+    //every answer of the key signatures drill counts, right or wrong
+    fn answer_key_drill(
+        &mut self,
+        answer: impl FnOnce(&mut KeySignatureDrill, &StaveGenerator) -> bool,
+    ) {
+        let right = answer(self.key_drill.as_mut().unwrap(), &self.generator);
+        if right {
             self.score.0 += 1;
+        }
+        self.score.1 += 1;
+    }
+
+    //the accidental is the heard one, from the key signature or an earlier note of the measure too
+    //a note without accidental is named natural
+    pub fn name_note(&mut self, pitch: Pitch, accidental: Accidental) {
+        if self.screen != Screen::Staves {
+            return;
+        }
+        if self.key_drill.is_some() {
+            return self.answer_key_drill(|d, g| d.answer_name(g, pitch, accidental));
+        }
+        if self.generator.exercise != Exercise::Names {
+            return;
+        }
+        let now = Instant::now();
+        let timing_error = self.get_timing_error(now);
+        if let Some(error) = timing_error {
+            if error < -self.get_timing_window_ms() {
+                self.score.1 += 1;
+                return;
+            }
+        }
 
-            searched_note.color = Color::GREEN;
-
-            self.current_measure_note.1 += 1;
-            if self.current_measure_note.1
-                == self
-                    .staves
-                    .get_mut(0)
-                    .unwrap()
-                    .measures
-                    .get(self.current_measure_note.0)
-                    .unwrap()
-                    .notes
-                    .len()
-            {
-                self.current_measure_note.0 += 1;
-                self.current_measure_note.1 = 0;
-
-                if self.current_measure_note.0 == self.staves.get_mut(0).unwrap().measures.len() {
-                    self.staves.remove(0);
-                    self.current_measure_note = (0, 0);
-
-                    // TODO: create new stave
-                    // measure
-                    self.staves.push(Stave::new_random(
-                        self.x_pos_stave,
-                        self.size_stave,
-                        Clef::Sol,
-                        KeySignature::new(KeySignatureAccidental::Sharp, 0),
-                    ));
+        //the notes of a chord are named in any order
+        let notes = self.get_searched_event_mut().notes.clone();
+        let found = (0..notes.len()).find(|i| {
+            !self.named.contains(i)
+                && notes[*i].pitch == pitch
+                && notes[*i].accidental.unwrap_or(Accidental::Natural) == accidental
+        });
+        let name = self.naming.get_ascii_name(pitch, Some(accidental));
+        self.last_named = Some((name, found.is_some()));
+        match found {
+            Some(i) => {
+                self.named.push(i);
+                if self.named.len() < notes.len() {
+                    return;
+                }
+                self.played_at = Some(now);
+                self.score.0 += 1;
+                if let Some(error) = timing_error {
+                    self.add_timing_result(Some(error));
                 }
+                self.validate_searched_event();
+            }
+            None => {
+                self.miss_searched_event(Vec::new());
+                if self.metronome.is_some() {
+                    self.pass_searched_event();
+                }
+            }
+        }
+        self.score.1 += 1;
+    }
+
+    //the notes of the interval holding the searched event and the number of its events from the searched one
+    //an event of two notes is a harmonic interval, two events of one note a melodic one
+    fn get_searched_interval(&self) -> (Vec<Note>, usize) {
+        let (i_measure, i_event) = self.current_measure_event;
+        let events = &self.staves[0].measures[i_measure].events;
+        let mut i = 0;
+        loop {
+            let nb = match events[i].notes.len() == 1 && i + 1 < events.len() {
+                true => 2,
+                false => 1,
+            };
+            if i_event < i + nb {
+                let notes = events[i..i + nb]
+                    .iter()
+                    .flat_map(|e| e.notes.clone())
+                    .collect();
+                return (notes, i + nb - i_event);
+            }
+            i += nb;
+        }
+    }
+
+    pub fn name_interval(&mut self, interval: Interval) {
+        if self.screen != Screen::Staves || self.generator.exercise != Exercise::IntervalNames {
+            return;
+        }
+        let now = Instant::now();
+        let timing_error = self.get_timing_error(now);
+        if let Some(error) = timing_error {
+            if error < -self.get_timing_window_ms() {
+                self.score.1 += 1;
+                return;
+            }
+        }
+
+        let (notes, nb_events) = self.get_searched_interval();
+        let right = notes.len() == 2 && Interval::between(&notes[0], &notes[1]) == Some(interval);
+        self.last_named = Some((interval.to_string(), right));
+        if right {
+            self.score.0 += 1;
+            if let Some(error) = timing_error {
+                self.add_timing_result(Some(error));
+            }
+            //both notes of a melodic interval are answered at once
+            let (searched_at, searched_missed) = (self.searched_at, self.searched_missed);
+            for _ in 0..nb_events {
+                self.searched_at = searched_at;
+                self.searched_missed = searched_missed;
+                self.played_at = Some(now);
+                self.validate_searched_event();
             }
-            //set the searched note GRAY
-            self.staves
-                .get_mut(0)
-                .unwrap()
-                .measures
-                .get_mut(self.current_measure_note.0)
-                .unwrap()
-                .notes
-                .get_mut(self.current_measure_note.1)
-                .unwrap()
-                .color = Color::GRAY;
         } else {
-            searched_note.color = Color::RED;
+            self.miss_searched_event(Vec::new());
+            if self.metronome.is_some() {
+                for _ in 0..nb_events {
+                    self.pass_searched_event();
+                }
+            }
         }
         self.score.1 += 1;
     }
 
-    fn released_semitone(&mut self, released_semitone: &Semitone) {
-        println!("released_semitone: {:?}", released_semitone);
-        println!("self.pressed_semitone: {:?}", self.pressed_semitone);
+    fn validate_searched_event(&mut self) {
+        if !self.searched_missed {
+            let played = self.get_searched_event_mut().get_semitones();
+            self.answer_searched_event(true, played);
+        }
+        self.get_searched_event_mut().set_color(Color::GREEN);
+        self.next_event();
+        self.set_searched_event();
+    }
+
+    //the searched event is wrong, its notes will come back soon
+    //played is what was played instead, empty when nothing was
+    fn miss_searched_event(&mut self, played: Vec<Semitone>) {
+        self.get_searched_event_mut().set_color(Color::RED);
+        if !self.searched_missed {
+            self.searched_missed = true;
+            self.answer_searched_event(false, played);
+        }
+    }
 
-        if self.pressed_semitone.as_ref() == Some(released_semitone) {
-            self.pressed_semitone = None;
+    //schedule the notes of the searched event for the next staves and keep the result in the session
+    fn answer_searched_event(&mut self, right: bool, played: Vec<Semitone>) {
+        //with the metronome the player waits for the cursor, the reaction time means nothing
+        let reaction_ms = match self.metronome {
+            Some(_) => None,
+            None => {
+                let played_at = self.played_at.unwrap_or_else(Instant::now);
+                Some(played_at.duration_since(self.searched_at).as_millis() as u32)
+            }
+        };
+        let keys: Vec<NoteKey> = self
+            .get_searched_event_mut()
+            .notes
+            .iter()
+            .map(NoteKey::new)
+            .collect();
+        for key in keys {
+            self.generator.repetition.answer(key, right, reaction_ms);
+            self.session.results.push(NoteResult {
+                key,
+                played: played.clone(),
+                right,
+                reaction_ms,
+            });
+        }
+    }
 
-            let searched_note = self
+    //a new stave comes when the first one is done
+    fn next_event(&mut self) {
+        self.current_measure_event.1 += 1;
+        if self.current_measure_event.1
+            == self
                 .staves
                 .get_mut(0)
                 .unwrap()
                 .measures
-                .get_mut(self.current_measure_note.0)
+                .get(self.current_measure_event.0)
                 .unwrap()
-                .notes
-                .get_mut(self.current_measure_note.1)
-                .unwrap();
-            searched_note.color = Color::GRAY;
+                .events
+                .len()
+        {
+            self.current_measure_event.0 += 1;
+            self.current_measure_event.1 = 0;
+
+            if self.current_measure_event.0 == self.staves.get_mut(0).unwrap().measures.len() {
+                let removed = self.staves.remove(0);
+                if let Some(m) = self.metronome.as_mut() {
+                    m.stave_start += m.get_tick_duration() * removed.get_ticks();
+                }
+                self.current_measure_event = (0, 0);
+                self.staves
+                    .push(self.generator.new_stave(self.x_pos_stave, self.size_stave));
+            }
         }
     }
 
+    //the searched event is the next one to play, rests and tied notes are passed
+    fn set_searched_event(&mut self) {
+        while !self.get_searched_event_mut().is_played() {
+            self.get_searched_event_mut().set_color(Color::GREEN);
+            self.next_event();
+        }
+
+        //set the searched event GRAY
+        self.get_searched_event_mut().set_color(Color::GRAY);
+        self.pedals.lifted = !self.pedals.sustain;
+        self.pedals.waiting = false;
+        self.wrong_semitones.clear();
+        self.searched_at = Instant::now();
+        self.searched_missed = false;
+        self.played_at = None;
+        self.named.clear();
+    }
+
+    pub fn released_semitone(&mut self, released_semitone: &Semitone) {
+        if self.pedal_mode == PedalMode::Sound && self.pedals.is_holding(released_semitone) {
+            for h in self.held_semitones.iter_mut() {
+                if h.semitone == *released_semitone {
+                    h.sustained = true;
+                }
+            }
+            return;
+        }
+        self.release_semitone(released_semitone);
+    }
+
+    fn release_semitone(&mut self, released_semitone: &Semitone) {
+        self.held_semitones
+            .retain(|h| h.semitone != *released_semitone);
+
+        if self.wrong_semitones.contains(released_semitone) {
+            self.wrong_semitones.retain(|s| s != released_semitone);
+            if self.wrong_semitones.is_empty() {
+                self.get_searched_event_mut().set_color(Color::GRAY);
+            }
+        }
+
+        if self.pedals.waiting
+            && self
+                .get_searched_event_mut()
+                .get_semitones()
+                .contains(released_semitone)
+        {
+            self.pedals.waiting = false;
+            self.get_searched_event_mut().set_color(Color::GRAY);
+        }
+    }
+
+    #[cfg(feature = "sdl")]
     pub fn draw(&self, canvas: &WindowCanvas) {
-        for (i, s) in self.staves.iter().enumerate() {
-            s.draw(40 + (i * 150) as i32, canvas);
+        match self.screen {
+            Screen::Staves => {}
+            Screen::Stats => return draw_stats(&self.get_sessions(), canvas),
+            Screen::Review => return self.review.as_ref().unwrap().draw(canvas),
+        }
+
+        match &self.key_drill {
+            Some(d) => d.draw(120, self.naming, canvas),
+            None => {
+                let mut y = 40;
+                for s in self.staves.iter() {
+                    s.draw(y, canvas);
+                    y += s.get_height() + if s.clefs.len() > 1 { 70 } else { 110 };
+                }
+            }
         }
 
         canvas.string(5,5, &((self.score.0).to_string()+"/"+&(self.score.1).to_string()), Color::BLACK).unwrap();
+        if self.is_quiz() {
+            let y = (self.y_pos_keyboard - 15) as i16;
+            let hint = match (self.key_drill.is_some(), self.naming) {
+                (true, _) => "Name the major or minor key or play its tonic",
+                (false, _) if self.generator.exercise == Exercise::IntervalNames => {
+                    "Name the interval: 2 to 8, shift minor, ctrl dim., alt aug."
+                }
+                (false, NoteNaming::German) => {
+                    "Name the note: letter, shift is, ctrl es, alt doubles them, H and B"
+                }
+                (false, _) => "Name the note: letter, shift #, ctrl b, alt doubles them",
+            };
+            canvas.string(self.x_pos_stave as i16, y, hint, Color::BLACK).unwrap();
+            if let (Some((name, right)), None) = (&self.last_named, &self.key_drill) {
+                let color = if *right { Color::GREEN } else { Color::RED };
+                canvas
+                    .string((self.x_pos_stave + 560) as i16, y, name, color)
+                    .unwrap();
+            }
+        }
+
+        //keys down, red when they are wrong
+        self.get_keyboard().draw(canvas, |s| {
+            if self.wrong_semitones.contains(&s) {
+                Some(Color::RED)
+            } else if self.held_semitones.iter().any(|h| h.semitone == s) {
+                Some(Color::BLUE)
+            } else {
+                None
+            }
+        });
+
+        //reading speed of the session
+        if let Some((mean, p90)) = get_reaction_stats(&self.session.results) {
+            canvas.string(5, 15, &format!("avg {}ms", mean), Color::BLACK).unwrap();
+            canvas.string(5, 25, &format!("p90 {}ms", p90), Color::BLACK).unwrap();
+        }
+        canvas.string(100, 5, &format!("F1: {}", self.generator.exercise), Color::BLACK).unwrap();
+        canvas.string(100, 15, &format!("F2: {}", self.generator.clef_policy), Color::BLACK).unwrap();
+        let rhythm = if self.generator.rhythm { "Rhythm" } else { "No rhythm" };
+        canvas.string(100, 25, &format!("F3: {}", rhythm), Color::BLACK).unwrap();
+
+        let adaptive = if self.generator.adaptive { "Adaptive notes" } else { "Random notes" };
+        canvas.string(400, 25, &format!("F5: {}", adaptive), Color::BLACK).unwrap();
+        canvas.string(600, 15, "F6: statistics", Color::BLACK).unwrap();
+        canvas.string(600, 25, "F7: review", Color::BLACK).unwrap();
+        let export = self.export_message.as_deref().unwrap_or("export");
+        canvas.string(600, 5, &format!("F8: {}", export), Color::BLACK).unwrap();
+        canvas.string(700, 25, &format!("F9: {}", self.naming), Color::BLACK).unwrap();
+
+        //timed mode
+        match &self.metronome {
+            Some(m) if self.key_drill.is_none() => {
+                canvas
+                    .string(
+                        400,
+                        5,
+                        &format!("F4: Timed {} bpm (up/down)", m.bpm),
+                        Color::BLACK,
+                    )
+                    .unwrap();
+                self.draw_timing_results(canvas);
+
+                //cursor
+                let elapsed_ms = m.get_elapsed_ms(Instant::now());
+                let tick_ms = m.get_tick_duration().as_secs_f32() * 1000.;
+                let stave = &self.staves[0];
+                let x = stave.get_ticks_x((elapsed_ms.max(0) as f32) / tick_ms);
+                canvas
+                    .thick_line(
+                        x as i16,
+                        30,
+                        x as i16,
+                        (50 + stave.get_height()) as i16,
+                        2,
+                        Color::BLUE,
+                    )
+                    .unwrap();
+
+                //beat
+                let beat_ms = (tick_ms * NoteValue::Quarter.get_ticks() as f32) as i64;
+                if elapsed_ms.rem_euclid(beat_ms) < 100 {
+                    canvas.filled_circle(785, 10, 5, Color::BLUE).unwrap();
+                }
+            }
+            _ => canvas
+                .string(400, 5, "F4: Wait for the right notes", Color::BLACK)
+                .unwrap(),
+        }
+
+        //pedals state
+        let mut pedals = Vec::new();
+        if self.pedals.sustain {
+            pedals.push("Ped.");
+        }
+        if self.pedals.sostenuto {
+            pedals.push("Sost.");
+        }
+        if self.pedals.soft {
+            pedals.push("u.c.");
+        }
+        canvas.string(300, 5, &pedals.join(" "), Color::BLACK).unwrap();
+    }
+
+    #[cfg(feature = "sdl")]
+    fn draw_timing_results(&self, canvas: &WindowCanvas) {
+        let count = |t: Timing| self.timing_results.iter().filter(|r| r.timing == t).count();
+        let errors: Vec<i32> = self
+            .timing_results
+            .iter()
+            .filter_map(|r| r.error_ms)
+            .collect();
+        let mean_error = match errors.len() {
+            0 => 0,
+            len => errors.iter().map(|e| e.abs()).sum::<i32>() / len as i32,
+        };
+        let s = format!(
+            "on time {} early {} late {} missed {} ({}ms)",
+            count(Timing::OnTime),
+            count(Timing::Early),
+            count(Timing::Late),
+            count(Timing::Missed),
+            mean_error
+        );
+        canvas.string(400, 15, &s, Color::BLACK).unwrap();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_searched_semitones(g: &Game) -> Vec<Semitone> {
+        let (i_measure, i_event) = g.current_measure_event;
+        g.staves[0].measures[i_measure].events[i_event].get_semitones()
+    }
+
+    #[test]
+    fn right_notes_score() {
+        let mut g = Game::new(800, 600, History::new());
+        g.start();
+        let semitones = get_searched_semitones(&g);
+        for s in semitones.iter() {
+            g.pressed_semitone(s);
+        }
+        assert_eq!(g.get_score(), (1, 1));
+        assert!(g.session.results.iter().all(|r| r.right));
+        for s in semitones.iter() {
+            g.released_semitone(s);
+        }
+    }
+
+    #[test]
+    fn wrong_chord_is_missed() {
+        let mut g = Game::new(800, 600, History::new());
+        g.generator.exercise = Exercise::Chords;
+        g.restart();
+        let mut semitones = get_searched_semitones(&g);
+        assert!(semitones.len() > 1);
+        //the highest note a semitone too high
+        let top = semitones.iter().map(|s| s.0).max().unwrap();
+        semitones.retain(|s| s.0 != top);
+        semitones.push(Semitone(top + 1));
+        for s in semitones.iter() {
+            g.pressed_semitone(s);
+        }
+        assert_eq!(g.get_score(), (0, 1));
+        assert!(!g.session.results.is_empty());
+        assert!(g.session.results.iter().all(|r| !r.right));
+        assert!(g.session.results[0].played.contains(&Semitone(top + 1)));
+        //the history stays in memory
+        g.save_session();
+        assert_eq!(g.history.sessions.len(), 1);
+    }
+
+    #[test]
+    fn other_screens_pause_the_game() {
+        let mut g = Game::new(800, 600, History::new());
+        g.toggle_timed();
+        let stave_start = g.metronome.as_ref().unwrap().stave_start;
+        g.toggle_stats();
+        for s in get_searched_semitones(&g) {
+            g.pressed_semitone(&s);
+        }
+        g.update();
+        assert_eq!(g.get_score(), (0, 0));
+        assert!(g.session.results.is_empty());
+        //five seconds on the statistics
+        g.left_staves_at = Instant::now().checked_sub(Duration::from_secs(5));
+        g.toggle_stats();
+        let m = g.metronome.as_ref().unwrap();
+        assert!(m.stave_start >= stave_start + Duration::from_secs(5));
+    }
+
+    //the searched event of the timed mode is due `late_ms` ago
+    fn set_due(g: &mut Game, late_ms: i32) {
+        let error = g.get_timing_error(Instant::now()).unwrap() - late_ms;
+        let m = g.metronome.as_mut().unwrap();
+        match error < 0 {
+            true => m.stave_start -= Duration::from_millis(-error as u64),
+            false => m.stave_start += Duration::from_millis(error as u64),
+        }
+    }
+
+    #[test]
+    fn timing_results() {
+        let mut g = Game::new(800, 600, History::new());
+        g.toggle_timed();
+        set_due(&mut g, 0);
+        for s in get_searched_semitones(&g) {
+            g.pressed_semitone(&s);
+        }
+        let r = g.get_timing_results();
+        assert_eq!(r.len(), 1);
+        assert_eq!(r[0].timing, Timing::OnTime);
+        assert!(r[0].error_ms.unwrap().abs() < ON_TIME_MS);
+
+        //nothing played for a second
+        set_due(&mut g, 1000);
+        g.update();
+        let r = g.get_timing_results();
+        assert_eq!(r.len(), 2);
+        assert_eq!((r[1].timing, r[1].error_ms), (Timing::Missed, None));
+        assert_eq!(g.get_score(), (1, 2));
     }
 }
@@ -0,0 +1,684 @@
+use rand::distributions::{Standard, WeightedIndex};
+use rand::rngs::ThreadRng;
+use rand::Rng;
+use std::fmt;
+
+use crate::graphics::Point;
+use crate::music::*;
+use crate::repetition::*;
+use crate::stave::*;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Exercise {
+    Notes,
+    //a line in a key, mostly by steps and ending on the tonic
+    Melody,
+    //play the notes and press/release the sustain pedal at the marks
+    Pedal,
+    Chords,
+    //only notes on and between the ledger lines
+    LedgerLines,
+    //the notes are named with the letter keys instead of played
+    Names,
+    //only a key signature, its major or minor key is named or its tonic played
+    KeySignatures,
+    //pairs of notes played one after the other or together
+    Intervals,
+    //the same pairs, their interval is named with the number keys
+    IntervalNames,
+}
+
+impl Exercise {
+    pub fn next(&self) -> Exercise {
+        match self {
+            Self::Notes => Self::Melody,
+            Self::Melody => Self::Pedal,
+            Self::Pedal => Self::Chords,
+            Self::Chords => Self::LedgerLines,
+            Self::LedgerLines => Self::Names,
+            Self::Names => Self::KeySignatures,
+            Self::KeySignatures => Self::Intervals,
+            Self::Intervals => Self::IntervalNames,
+            Self::IntervalNames => Self::Notes,
+        }
+    }
+}
+
+impl fmt::Display for Exercise {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ClefPolicy {
+    //Sol or Fa for each stave
+    Random,
+    Fixed(Clef),
+    //Sol and Fa joined, both hands
+    Grand,
+}
+
+impl ClefPolicy {
+    pub fn next(&self) -> ClefPolicy {
+        match self {
+            Self::Random => Self::Fixed(Clef::Sol),
+            Self::Fixed(Clef::Sol) => Self::Fixed(Clef::Fa),
+            Self::Fixed(Clef::Fa) => Self::Grand,
+            Self::Grand => Self::Random,
+        }
+    }
+}
+
+impl fmt::Display for ClefPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Random => write!(f, "Random clef"),
+            Self::Fixed(c) => write!(f, "{}", c),
+            Self::Grand => write!(f, "Grand staff"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum KeySignaturePolicy {
+    Random,
+    Fixed(KeySignature),
+    //the signature of the key and only the notes of its scale
+    Key(Key),
+}
+
+//the lines written in the melody exercise, each clef has its own
+struct Melody {
+    key: Key,
+    //steps the notes of each clef can be on
+    steps: Vec<Vec<i32>>,
+    //last step of each clef and the interval to it
+    previous: Vec<Option<(i32, i32)>>,
+}
+
+impl Melody {
+    //only the steps whose note of the scale is a midi note
+    //a range without any keeps its steps, their notes are then written natural
+    fn new(key: Key, steps: Vec<Vec<i32>>) -> Melody {
+        let steps: Vec<Vec<i32>> = steps
+            .into_iter()
+            .map(|clef_steps| {
+                let playable: Vec<i32> = clef_steps
+                    .iter()
+                    .copied()
+                    .filter(|s| is_playable(&Melody::get_note(&key, *s), key.get_key_signature()))
+                    .collect();
+                if playable.is_empty() {
+                    clef_steps
+                } else {
+                    playable
+                }
+            })
+            .collect();
+        let previous = vec![None; steps.len()];
+        Melody {
+            key,
+            steps,
+            previous,
+        }
+    }
+
+    fn get_degree(&self, step: i32) -> i32 {
+        self.key.get_degree_of(from_step(step).0)
+    }
+
+    //the note of the scale on the step, the clef is set by the caller
+    fn get_note(key: &Key, step: i32) -> Note {
+        let (pitch, octave) = from_step(step);
+        let (_, accidental) = key.get_degree(key.get_degree_of(pitch));
+        Note::new(pitch, accidental, octave, Clef::Sol)
+    }
+
+    //the last event of the stave ends on the tonic
+    fn new_event(
+        &mut self,
+        rng: &mut ThreadRng,
+        clefs: &[Clef],
+        length: NoteLength,
+        last: bool,
+    ) -> MeasureEvent {
+        let mut notes = Vec::new();
+        for (i, clef) in clefs.iter().enumerate() {
+            let step = self.new_step(rng, i, last);
+            let interval = self.previous[i].map(|(p, _)| step - p).unwrap_or(0);
+            self.previous[i] = Some((step, interval));
+
+            let mut n = Melody::get_note(&self.key, step);
+            n.clef = *clef;
+            if !is_playable(&n, self.key.get_key_signature()) {
+                n.accidental = Some(Accidental::Natural);
+            }
+            notes.push(n);
+        }
+        MeasureEvent::new(notes, length)
+    }
+
+    fn new_step(&self, rng: &mut ThreadRng, i_clef: usize, last: bool) -> i32 {
+        let steps = &self.steps[i_clef];
+        let (previous, interval) = match self.previous[i_clef] {
+            //starts on the tonic, the third or the fifth
+            None => {
+                let weights = steps.iter().map(|s| match self.get_degree(*s) {
+                    1 => 3,
+                    3 | 5 => 2,
+                    _ => 0,
+                });
+                return match WeightedIndex::new(weights) {
+                    Ok(w) => steps[rng.sample(w)],
+                    Err(_) => steps[rng.gen_range(0..steps.len())],
+                };
+            }
+            Some(p) => p,
+        };
+
+        //the nearest tonic
+        if last {
+            let tonic = steps
+                .iter()
+                .filter(|s| self.get_degree(**s) == 1)
+                .min_by_key(|s| (**s - previous).abs());
+            if let Some(t) = tonic {
+                return *t;
+            }
+        }
+
+        //the leading tone goes up to the tonic
+        if self.key.has_leading_tone()
+            && self.get_degree(previous) == 7
+            && steps.contains(&(previous + 1))
+        {
+            return previous + 1;
+        }
+
+        //mostly steps, leaps up to a sixth, a leap is followed by a step back
+        let weights: Vec<u32> = steps
+            .iter()
+            .map(|s| {
+                let d = s - previous;
+                let w = match d.abs() {
+                    0 => 2,
+                    1 => 10,
+                    2 => 6,
+                    3 => 3,
+                    4 => 2,
+                    5 => 1,
+                    _ => 0,
+                };
+                if interval.abs() >= 3 && d.abs() <= 2 && d.signum() == -interval.signum() {
+                    w * 3
+                } else {
+                    w
+                }
+            })
+            .collect();
+        match WeightedIndex::new(weights) {
+            Ok(w) => steps[rng.sample(w)],
+            Err(_) => steps[rng.gen_range(0..steps.len())],
+        }
+    }
+}
+
+//settings of the exercises of a session, every stave of the session is made by it
+pub struct StaveGenerator {
+    pub exercise: Exercise,
+    pub clef_policy: ClefPolicy,
+    pub key_signature_policy: KeySignaturePolicy,
+    //note lengths, rests and time signatures, or only quarter notes in 4/4
+    pub rhythm: bool,
+    //lowest and highest notes of each clef
+    pub sol_range: NoteRange,
+    pub fa_range: NoteRange,
+    //ledger lines above and below the stave in the ledger lines drill
+    pub ledger_lines: u8,
+    pub pitch_weights: PitchWeights,
+    pub accidental_weights: AccidentalWeights,
+    pub key_signature_weights: KeySignatureWeights,
+    //the notes the player misses or plays slowly come more often
+    pub adaptive: bool,
+    pub repetition: Repetition,
+}
+
+impl Default for StaveGenerator {
+    fn default() -> Self {
+        StaveGenerator::new()
+    }
+}
+
+impl StaveGenerator {
+    pub fn new() -> StaveGenerator {
+        StaveGenerator {
+            exercise: Exercise::Notes,
+            clef_policy: ClefPolicy::Random,
+            key_signature_policy: KeySignaturePolicy::Random,
+            rhythm: false,
+            sol_range: NoteRange::around_stave(Clef::Sol, 1, 1),
+            fa_range: NoteRange::around_stave(Clef::Fa, 1, 1),
+            ledger_lines: 3,
+            pitch_weights: PitchWeights::default(),
+            accidental_weights: AccidentalWeights::default(),
+            key_signature_weights: KeySignatureWeights::default(),
+            adaptive: true,
+            repetition: Repetition::new(),
+        }
+    }
+
+    //grand staves are higher, less of them fit
+    pub fn get_nb_staves(&self) -> usize {
+        match self.clef_policy {
+            ClefPolicy::Grand => 3,
+            _ => 4,
+        }
+    }
+
+    fn get_clefs(&self, rng: &mut ThreadRng) -> Vec<Clef> {
+        match self.clef_policy {
+            ClefPolicy::Random => vec![rng.sample(Standard)],
+            ClefPolicy::Fixed(c) => vec![c],
+            ClefPolicy::Grand => vec![Clef::Sol, Clef::Fa],
+        }
+    }
+
+    fn get_key_signature(&self, rng: &mut ThreadRng) -> KeySignature {
+        match self.key_signature_policy {
+            KeySignaturePolicy::Random => rng.sample(self.key_signature_weights),
+            KeySignaturePolicy::Fixed(k) => k,
+            KeySignaturePolicy::Key(k) => k.get_key_signature(),
+        }
+    }
+
+    //the key of the policy, or a major or minor key of the key signature
+    fn get_key(&self, rng: &mut ThreadRng, key_signature: KeySignature) -> Key {
+        if let KeySignaturePolicy::Key(k) = self.key_signature_policy {
+            return k;
+        }
+        if rng.gen_bool(0.5) {
+            let (pitch, accidental) = key_signature.get_major_tonic();
+            return Key::new(pitch, accidental, Mode::Major).unwrap();
+        }
+        //with the raised leading tone
+        let (pitch, accidental) = key_signature.get_minor_tonic();
+        Key::new(pitch, accidental, Mode::HarmonicMinor).unwrap()
+    }
+
+    pub fn get_range(&self, clef: Clef) -> NoteRange {
+        match clef {
+            Clef::Sol => self.sol_range,
+            Clef::Fa => self.fa_range,
+        }
+    }
+
+    pub fn set_range(&mut self, clef: Clef, range: NoteRange) {
+        match clef {
+            Clef::Sol => self.sol_range = range,
+            Clef::Fa => self.fa_range = range,
+        }
+    }
+
+    //steps the notes of a clef can be on
+    fn get_steps(&self, clef: Clef) -> Vec<i32> {
+        match self.exercise {
+            Exercise::LedgerLines => {
+                NoteRange::around_stave(clef, self.ledger_lines, self.ledger_lines)
+                    .get_steps()
+                    .filter(|s| clef.needs_ledger_lines(*s))
+                    .collect()
+            }
+            _ => self.get_range(clef).get_steps().collect(),
+        }
+    }
+
+    //lowest and highest semitones the staves can hold, with their accidentals, inside the midi notes
+    pub fn get_semitone_range(&self) -> (Semitone, Semitone) {
+        let clefs = match self.clef_policy {
+            ClefPolicy::Fixed(c) => vec![c],
+            _ => vec![Clef::Sol, Clef::Fa],
+        };
+        let semitones: Vec<u8> = clefs
+            .iter()
+            .flat_map(|c| {
+                self.get_steps(*c).into_iter().filter_map(|s| {
+                    let (pitch, octave) = from_step(s);
+                    Note::new(pitch, None, octave, *c)
+                        .get_semitone()
+                        .map(|s| s.0)
+                })
+            })
+            .collect();
+        (
+            Semitone(semitones.iter().min().unwrap().saturating_sub(1)),
+            Semitone((semitones.iter().max().unwrap() + 1).min(127)),
+        )
+    }
+
+    //without rhythm every measure is four quarter notes
+    pub fn new_stave(&self, x_pos: i32, size: Point) -> Stave {
+        let mut rng = rand::thread_rng();
+        let time_signature = match self.rhythm {
+            true => [
+                TimeSignature(4, NoteValue::Quarter),
+                TimeSignature(3, NoteValue::Quarter),
+                TimeSignature(2, NoteValue::Quarter),
+                TimeSignature(6, NoteValue::Eighth),
+                TimeSignature(3, NoteValue::Eighth),
+            ][rng.gen_range(0..5)],
+            false => TimeSignature(4, NoteValue::Quarter),
+        };
+        let clefs = self.get_clefs(&mut rng);
+        let key_signature = self.get_key_signature(&mut rng);
+        let mut melody = match self.exercise {
+            Exercise::Melody => {
+                let steps = clefs.iter().map(|c| self.get_steps(*c)).collect();
+                Some(Melody::new(self.get_key(&mut rng, key_signature), steps))
+            }
+            _ => None,
+        };
+        let mut s = Stave::new(x_pos, size, clefs, key_signature, time_signature);
+        //rests and ties would skip the pedal marks and split the intervals
+        let intervals = matches!(self.exercise, Exercise::Intervals | Exercise::IntervalNames);
+        let rests_ties = self.rhythm && self.exercise != Exercise::Pedal && !intervals;
+
+        let nb_measures = 3;
+        for i_m in 0..nb_measures {
+            let mut events = Vec::new();
+            //second note of a melodic interval, the intervals stay in their measure
+            let mut second: Option<Note> = None;
+            let lengths = self.new_lengths(&mut rng, time_signature);
+
+            //tie the last notes of the previous measure over the bar line, not over the end of the melody
+            let ending = melody.is_some() && i_m == nb_measures - 1 && lengths.len() == 1;
+            let tied_notes = match s.measures.last() {
+                Some(m) if rests_ties && !ending && rng.gen_bool(0.2) => {
+                    Some(m.events.last().unwrap().notes.clone()).filter(|n| !n.is_empty())
+                }
+                _ => None,
+            };
+
+            for (i_e, length) in lengths.iter().enumerate() {
+                //the melody ends on a note
+                let last = i_m == nb_measures - 1 && i_e == lengths.len() - 1;
+                if let (0, Some(notes)) = (i_e, &tied_notes) {
+                    let mut e = MeasureEvent::new(notes.clone(), *length);
+                    e.tied = true;
+                    events.push(e);
+                } else if rests_ties && !(last && melody.is_some()) && rng.gen_bool(0.15) {
+                    events.push(MeasureEvent::new(Vec::new(), *length));
+                } else if let Some(m) = melody.as_mut() {
+                    events.push(m.new_event(&mut rng, &s.clefs, *length, last));
+                } else if let Some(n) = second.take() {
+                    events.push(MeasureEvent::new(vec![n], *length));
+                } else if intervals {
+                    let melodic = i_e + 1 < lengths.len() && rng.gen_bool(0.5);
+                    let (a, b) = self.new_interval(&mut rng, &s.clefs, key_signature, melodic);
+                    if melodic {
+                        events.push(MeasureEvent::new(vec![a], *length));
+                        second = Some(b);
+                    } else {
+                        events.push(MeasureEvent::new(vec![a, b], *length));
+                    }
+                } else {
+                    events.push(self.new_event(&mut rng, &s.clefs, key_signature, *length));
+                }
+            }
+            s.add_measure(Measure::new(events, key_signature, time_signature).unwrap());
+        }
+
+        if self.exercise == Exercise::Pedal {
+            //pedal at the first note, change it at each measure and release it at the end
+            for (i, m) in s.measures.iter_mut().enumerate() {
+                m.events.first_mut().unwrap().pedal = match i {
+                    0 => Some(PedalMark::Press),
+                    _ => Some(PedalMark::Change),
+                };
+            }
+            s.measures
+                .last_mut()
+                .unwrap()
+                .events
+                .last_mut()
+                .unwrap()
+                .pedal = Some(PedalMark::Release);
+        }
+        s
+    }
+
+    //the clefs and a key signature without measures, every key signature is as likely
+    pub fn new_key_signature_stave(&self, x_pos: i32, size: Point) -> Stave {
+        let mut rng = rand::thread_rng();
+        let key_signature = match rng.gen_range(0..15) {
+            i if i < 8 => KeySignature::new(KeySignatureAccidental::Sharp, i),
+            i => KeySignature::new(KeySignatureAccidental::Flat, i - 7),
+        };
+        let clefs = self.get_clefs(&mut rng);
+        let mut s = Stave::new(
+            x_pos,
+            size,
+            clefs,
+            key_signature,
+            TimeSignature(4, NoteValue::Quarter),
+        );
+        s.draw_time_signature = false;
+        s
+    }
+
+    //lengths filling a measure
+    fn new_lengths(&self, rng: &mut ThreadRng, time_signature: TimeSignature) -> Vec<NoteLength> {
+        if !self.rhythm {
+            return vec![NoteLength::new(time_signature.1, false); time_signature.0 as usize];
+        }
+
+        //(length, weight)
+        let candidates = [
+            (NoteLength::new(NoteValue::Whole, false), 1),
+            (NoteLength::new(NoteValue::Half, true), 1),
+            (NoteLength::new(NoteValue::Half, false), 3),
+            (NoteLength::new(NoteValue::Quarter, true), 2),
+            (NoteLength::new(NoteValue::Quarter, false), 6),
+            (NoteLength::new(NoteValue::Eighth, true), 1),
+            (NoteLength::new(NoteValue::Eighth, false), 4),
+            (NoteLength::new(NoteValue::Sixteenth, false), 1),
+        ];
+
+        let mut lengths = Vec::new();
+        let mut remaining = time_signature.get_measure_ticks();
+        while remaining > 0 {
+            //every tick count is even, a sixteenth always fits
+            let fitting: Vec<&(NoteLength, u32)> = candidates
+                .iter()
+                .filter(|(l, _)| l.get_ticks() <= remaining)
+                .collect();
+            let total: u32 = fitting.iter().map(|(_, w)| w).sum();
+            let mut r = rng.gen_range(0..total);
+            for (l, w) in fitting {
+                if r < *w {
+                    lengths.push(*l);
+                    remaining -= l.get_ticks();
+                    break;
+                }
+                r -= w;
+            }
+        }
+        lengths
+    }
+
+    //notes of every clef at the same beat, both hands on a grand staff
+    fn new_event(
+        &self,
+        rng: &mut ThreadRng,
+        clefs: &[Clef],
+        key_signature: KeySignature,
+        length: NoteLength,
+    ) -> MeasureEvent {
+        let mut notes = Vec::new();
+        for (i, clef) in clefs.iter().enumerate() {
+            match self.exercise {
+                //the chord is on the upper stave, the bass stays a single note
+                Exercise::Chords if i == 0 => {
+                    //stacked thirds from a random root, triads and some intervals of a third
+                    let root = self.new_note(rng, *clef, key_signature);
+                    notes.push(root);
+                    let steps = match rng.gen_bool(0.7) {
+                        true => vec![2, 4],
+                        false => vec![2],
+                    };
+                    for s in steps {
+                        notes.extend(match self.key_signature_policy {
+                            //the notes of the scale, as the raised leading tone of the minor keys
+                            KeySignaturePolicy::Key(k) => k.get_above(&root, s),
+                            _ => Some(root.get_above(s)).filter(|t| is_playable(t, key_signature)),
+                        });
+                    }
+                }
+                _ => notes.push(self.new_note(rng, *clef, key_signature)),
+            }
+        }
+        MeasureEvent::new(notes, length)
+    }
+
+    //accidentals a pitch can have, only the one of the scale in a key
+    fn get_accidentals(&self, pitch: Pitch) -> Vec<Option<Accidental>> {
+        match self.key_signature_policy {
+            KeySignaturePolicy::Key(k) => {
+                let (_, accidental) = k
+                    .get_scale()
+                    .into_iter()
+                    .find(|(p, _)| *p == pitch)
+                    .unwrap();
+                vec![accidental]
+            }
+            _ => vec![
+                None,
+                Some(Accidental::Sharp),
+                Some(Accidental::Flat),
+                Some(Accidental::Natural),
+            ],
+        }
+    }
+
+    //a note and another one a common interval above or below it in the range
+    //seconds are only melodic, their notes would be drawn over each other
+    fn new_interval(
+        &self,
+        rng: &mut ThreadRng,
+        clefs: &[Clef],
+        key_signature: KeySignature,
+        melodic: bool,
+    ) -> (Note, Note) {
+        let clef = clefs[rng.gen_range(0..clefs.len())];
+        let steps = self.get_steps(clef);
+        let intervals: Vec<Interval> = Interval::get_common()
+            .into_iter()
+            .filter(|i| melodic || i.get_number() > 2)
+            .collect();
+
+        //the accidental of the key signature is needed to count the semitones
+        let n = self
+            .new_note(rng, clef, key_signature)
+            .get_sounding(key_signature);
+        for _ in 0..20 {
+            let interval = intervals[rng.gen_range(0..intervals.len())];
+            let other = match rng.gen_bool(0.5) {
+                true => interval.get_above(&n),
+                false => interval.get_below(&n),
+            };
+            if let Some(o) = other.filter(|o| steps.contains(&o.get_step())) {
+                return (n, o);
+            }
+        }
+        //a range too small for the intervals
+        match is_playable(&n.get_above(2), key_signature) {
+            true => (n, n.get_above(2)),
+            false => (n, n.get_above(-2)),
+        }
+    }
+
+    fn new_note(&self, rng: &mut ThreadRng, clef: Clef, key_signature: KeySignature) -> Note {
+        let steps = self.get_steps(clef);
+        if self.adaptive {
+            //every note which can be written, weighted by the schedule of the player
+            let mut notes = Vec::new();
+            let mut weights = Vec::new();
+            for s in steps.iter() {
+                let (pitch, octave) = from_step(*s);
+                for accidental in self.get_accidentals(pitch) {
+                    let n = Note::new(pitch, accidental, octave, clef);
+                    if !is_playable(&n, key_signature) {
+                        continue;
+                    }
+                    //the cards are on the notes as they sound, as the game answers them
+                    let key = NoteKey::new(&n.get_sounding(key_signature));
+                    weights.push(
+                        self.pitch_weights.get_weight(pitch)
+                            * self.accidental_weights.get_weight(accidental)
+                            * self.repetition.get_factor(&key),
+                    );
+                    notes.push(n);
+                }
+            }
+            if let Ok(w) = WeightedIndex::new(weights) {
+                return notes[rng.sample(w)];
+            }
+        }
+
+        let weights = steps
+            .iter()
+            .map(|s| self.pitch_weights.get_weight(from_step(*s).0));
+        //the weighted pitches can all be out of the range, then every step is as likely
+        let i_step = match WeightedIndex::new(weights) {
+            Ok(w) => rng.sample(w),
+            Err(_) => rng.gen_range(0..steps.len()),
+        };
+        let (pitch, octave) = from_step(steps[i_step]);
+        let accidental = match self.key_signature_policy {
+            KeySignaturePolicy::Key(_) => self.get_accidentals(pitch)[0],
+            _ => rng.sample(self.accidental_weights),
+        };
+        let n = Note::new(pitch, accidental, octave, clef);
+        match is_playable(&n, key_signature) {
+            true => n,
+            //the natural of a step of the range is a midi note
+            false => Note::new(pitch, Some(Accidental::Natural), octave, clef),
+        }
+    }
+}
+
+//a midi note once the key signature is applied
+fn is_playable(n: &Note, key_signature: KeySignature) -> bool {
+    n.get_sounding(key_signature).get_semitone().is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn semitone_range_at_the_ends_of_the_midi_notes() {
+        let mut generator = StaveGenerator::new();
+        generator.clef_policy = ClefPolicy::Fixed(Clef::Sol);
+        let range = NoteRange::new((Pitch::C, Octave(-2)), (Pitch::G, Octave(8))).unwrap();
+        generator.set_range(Clef::Sol, range);
+        assert_eq!(generator.get_semitone_range(), (Semitone(0), Semitone(127)));
+    }
+
+    #[test]
+    fn melody_in_a_range_out_of_the_key() {
+        let mut generator = StaveGenerator::new();
+        generator.exercise = Exercise::Melody;
+        generator.clef_policy = ClefPolicy::Fixed(Clef::Sol);
+        let range = NoteRange::new((Pitch::C, Octave(-2)), (Pitch::C, Octave(-2))).unwrap();
+        generator.set_range(Clef::Sol, range);
+        let key = Key::new(Pitch::C, Some(Accidental::Flat), Mode::Major).unwrap();
+        generator.key_signature_policy = KeySignaturePolicy::Key(key);
+        let stave = generator.new_stave(0, Point::new(800, 50));
+        for e in stave.measures.iter().flat_map(|m| m.events.iter()) {
+            for n in e.notes.iter() {
+                assert_eq!(n.get_semitone(), Some(Semitone(0)));
+            }
+        }
+    }
+}
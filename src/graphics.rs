@@ -0,0 +1,48 @@
+//positions and colors of the game, the same as the sdl2 ones so the core builds without sdl2
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct Point {
+    pub x: i32,
+    pub y: i32,
+}
+
+impl Point {
+    pub fn new(x: i32, y: i32) -> Point {
+        Point { x, y }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl Color {
+    pub const WHITE: Color = Color::RGB(255, 255, 255);
+    pub const BLACK: Color = Color::RGB(0, 0, 0);
+    pub const GRAY: Color = Color::RGB(128, 128, 128);
+    pub const RED: Color = Color::RGB(255, 0, 0);
+    pub const GREEN: Color = Color::RGB(0, 255, 0);
+    pub const BLUE: Color = Color::RGB(0, 0, 255);
+
+    #[allow(non_snake_case)]
+    pub const fn RGB(r: u8, g: u8, b: u8) -> Color {
+        Color { r, g, b }
+    }
+}
+
+#[cfg(feature = "sdl")]
+impl sdl2::gfx::primitives::ToColor for Color {
+    fn as_rgba(&self) -> (u8, u8, u8, u8) {
+        (self.r, self.g, self.b, 255)
+    }
+}
+
+#[cfg(feature = "sdl")]
+impl From<Color> for sdl2::pixels::Color {
+    fn from(c: Color) -> sdl2::pixels::Color {
+        sdl2::pixels::Color::RGB(c.r, c.g, c.b)
+    }
+}
@@ -0,0 +1,242 @@
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::music::*;
+use crate::repetition::NoteKey;
+
+//every session is appended to a text file in the data directory of the user
+//session <start in seconds since 1970> <settings>
+//note <clef> <pitch> <accidental or -> <octave> <1 right, 0 missed> <reaction in ms or -> <played semitones as 60,64 or ->
+//the played semitones were added later, older lines have 6 words
+const FILE_NAME: &str = "history.txt";
+
+#[derive(Debug, Clone)]
+pub struct NoteResult {
+    //the written note
+    pub key: NoteKey,
+    //keys played for the event of the note, the notes of a chord share them
+    pub played: Vec<Semitone>,
+    pub right: bool,
+    //None in timed mode
+    pub reaction_ms: Option<u32>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Session {
+    //seconds since 1970 (UTC)
+    pub start: u64,
+    //exercise, clef, rhythm... as shown in the game
+    pub settings: String,
+    pub results: Vec<NoteResult>,
+}
+
+pub struct History {
+    //None keeps the history in memory, without any file
+    path: Option<PathBuf>,
+    pub sessions: Vec<Session>,
+}
+
+pub fn get_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+//$XDG_DATA_HOME/train_piano, ~/.local/share/train_piano or %APPDATA%\train_piano
+pub fn get_data_dir() -> Option<PathBuf> {
+    let base = match std::env::var_os("XDG_DATA_HOME") {
+        Some(d) if !d.is_empty() => PathBuf::from(d),
+        _ => match std::env::var_os("APPDATA") {
+            Some(d) => PathBuf::from(d),
+            None => PathBuf::from(std::env::var_os("HOME")?).join(".local/share"),
+        },
+    };
+    Some(base.join("train_piano"))
+}
+
+impl Session {
+    pub fn new(settings: String) -> Session {
+        Session {
+            start: get_now(),
+            settings,
+            results: Vec::new(),
+        }
+    }
+
+    fn to_lines(&self) -> String {
+        let mut s = format!("session {} {}\n", self.start, self.settings);
+        for r in self.results.iter() {
+            let accidental = match r.key.accidental {
+                Some(a) => format!("{:?}", a),
+                None => "-".to_string(),
+            };
+            let reaction = match r.reaction_ms {
+                Some(ms) => ms.to_string(),
+                None => "-".to_string(),
+            };
+            let played = match r.played.is_empty() {
+                true => "-".to_string(),
+                false => r
+                    .played
+                    .iter()
+                    .map(|p| p.0.to_string())
+                    .collect::<Vec<String>>()
+                    .join(","),
+            };
+            s += &format!(
+                "note {:?} {:?} {} {} {} {} {}\n",
+                r.key.clef,
+                r.key.pitch,
+                accidental,
+                r.key.octave.0,
+                r.right as u8,
+                reaction,
+                played
+            );
+        }
+        s
+    }
+}
+
+fn parse_clef(s: &str) -> Option<Clef> {
+    match s {
+        "Sol" => Some(Clef::Sol),
+        "Fa" => Some(Clef::Fa),
+        _ => None,
+    }
+}
+
+fn parse_pitch(s: &str) -> Option<Pitch> {
+    match s {
+        "A" => Some(Pitch::A),
+        "B" => Some(Pitch::B),
+        "C" => Some(Pitch::C),
+        "D" => Some(Pitch::D),
+        "E" => Some(Pitch::E),
+        "F" => Some(Pitch::F),
+        "G" => Some(Pitch::G),
+        _ => None,
+    }
+}
+
+//Some(None) for "-", no accidental
+fn parse_accidental(s: &str) -> Option<Option<Accidental>> {
+    match s {
+        "-" => Some(None),
+        "Sharp" => Some(Some(Accidental::Sharp)),
+        "Flat" => Some(Some(Accidental::Flat)),
+        "Natural" => Some(Some(Accidental::Natural)),
+        "DoubleSharp" => Some(Some(Accidental::DoubleSharp)),
+        "DoubleFlat" => Some(Some(Accidental::DoubleFlat)),
+        _ => None,
+    }
+}
+
+fn parse_note_result(words: &[&str]) -> Option<NoteResult> {
+    let played = match words.len() {
+        6 => Vec::new(),
+        7 if words[6] == "-" => Vec::new(),
+        7 => words[6]
+            .split(',')
+            .map(|p| p.parse().ok().map(Semitone))
+            .collect::<Option<Vec<Semitone>>>()?,
+        _ => return None,
+    };
+    let key = NoteKey {
+        clef: parse_clef(words[0])?,
+        pitch: parse_pitch(words[1])?,
+        accidental: parse_accidental(words[2])?,
+        octave: Octave(words[3].parse().ok()?),
+    };
+    //a note out of the midi range has no semitone
+    get_semitone(key.pitch, key.accidental, key.octave)?;
+    Some(NoteResult {
+        key,
+        played,
+        right: words[4] == "1",
+        reaction_ms: words[5].parse().ok(),
+    })
+}
+
+impl Default for History {
+    fn default() -> Self {
+        History::new()
+    }
+}
+
+impl History {
+    pub fn new() -> History {
+        History {
+            path: None,
+            sessions: Vec::new(),
+        }
+    }
+
+    //a missing or unreadable file is an empty history, the sessions added later are appended to it
+    //with the problems met, the wrong lines are skipped
+    pub fn load() -> (History, Vec<String>) {
+        let path = get_data_dir().map(|d| d.join(FILE_NAME));
+        let mut problems = Vec::new();
+        if path.is_none() {
+            problems.push("no data directory, the history is only kept in memory".to_string());
+        }
+        let mut history = History {
+            path,
+            sessions: Vec::new(),
+        };
+        let content = match history.path.as_ref().map(fs::read_to_string) {
+            Some(Ok(c)) => c,
+            _ => return (history, problems),
+        };
+
+        for (i, line) in content.lines().enumerate() {
+            let words: Vec<&str> = line.split_whitespace().collect();
+            match words.first() {
+                Some(&"session") => {
+                    let start = words.get(1).and_then(|s| s.parse().ok()).unwrap_or(0);
+                    let settings = words.get(2..).unwrap_or_default().join(" ");
+                    history.sessions.push(Session {
+                        start,
+                        settings,
+                        results: Vec::new(),
+                    });
+                }
+                Some(&"note") => {
+                    match (parse_note_result(&words[1..]), history.sessions.last_mut()) {
+                        (Some(r), Some(s)) => s.results.push(r),
+                        _ => problems.push(format!("history: wrong line {}: {}", i + 1, line)),
+                    }
+                }
+                None => {}
+                _ => problems.push(format!("history: wrong line {}: {}", i + 1, line)),
+            }
+        }
+        (history, problems)
+    }
+
+    //the session is appended to the file if any, sessions without any note are not kept
+    pub fn add_session(&mut self, session: Session) -> Result<(), String> {
+        if session.results.is_empty() {
+            return Ok(());
+        }
+        let lines = session.to_lines();
+        self.sessions.push(session);
+
+        let path = match self.path.as_ref() {
+            Some(p) => p,
+            None => return Ok(()),
+        };
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| e.to_string())?;
+        file.write_all(lines.as_bytes()).map_err(|e| e.to_string())
+    }
+}
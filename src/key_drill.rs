@@ -0,0 +1,101 @@
+#[cfg(feature = "sdl")]
+use sdl2::gfx::primitives::DrawRenderer;
+#[cfg(feature = "sdl")]
+use sdl2::render::WindowCanvas;
+
+use crate::generator::StaveGenerator;
+#[cfg(feature = "sdl")]
+use crate::graphics::Color;
+use crate::graphics::Point;
+use crate::music::*;
+use crate::stave::*;
+
+//a clef and a key signature, the player names its major or relative minor key or plays its tonic
+pub struct KeySignatureDrill {
+    stave: Stave,
+    //key signature of the previous question and whether it was found
+    last: Option<(KeySignature, bool)>,
+}
+
+//as D major / B minor
+#[cfg(feature = "sdl")]
+fn get_keys_name(key_signature: &KeySignature, naming: NoteNaming) -> String {
+    let (major, major_accidental) = key_signature.get_major_tonic();
+    let (minor, minor_accidental) = key_signature.get_minor_tonic();
+    format!(
+        "{} major / {} minor",
+        naming.get_ascii_name(major, major_accidental),
+        naming.get_ascii_name(minor, minor_accidental)
+    )
+}
+
+impl KeySignatureDrill {
+    pub fn new(generator: &StaveGenerator, x_pos: i32, size: Point) -> KeySignatureDrill {
+        KeySignatureDrill {
+            stave: generator.new_key_signature_stave(x_pos, size),
+            last: None,
+        }
+    }
+
+    fn get_tonics(&self) -> [(Pitch, Option<Accidental>); 2] {
+        let key_signature = self.stave.key_signature;
+        [
+            key_signature.get_major_tonic(),
+            key_signature.get_minor_tonic(),
+        ]
+    }
+
+    //a new key signature comes after each answer
+    fn next(&mut self, generator: &StaveGenerator, right: bool) {
+        self.last = Some((self.stave.key_signature, right));
+        self.stave = generator.new_key_signature_stave(self.stave.x_pos, self.stave.size);
+    }
+
+    //a tonic without accidental is named natural
+    pub fn answer_name(
+        &mut self,
+        generator: &StaveGenerator,
+        pitch: Pitch,
+        accidental: Accidental,
+    ) -> bool {
+        let right = self
+            .get_tonics()
+            .iter()
+            .any(|(p, a)| *p == pitch && a.unwrap_or(Accidental::Natural) == accidental);
+        self.next(generator, right);
+        right
+    }
+
+    //the tonic in any octave
+    pub fn answer_semitone(&mut self, generator: &StaveGenerator, semitone: Semitone) -> bool {
+        let clef = self.stave.clefs[0];
+        let right = self.get_tonics().iter().any(|(p, a)| {
+            Note::new(*p, *a, Octave(4), clef)
+                .get_semitone()
+                .is_some_and(|s| s.0 % 12 == semitone.0 % 12)
+        });
+        self.next(generator, right);
+        right
+    }
+
+    #[cfg(feature = "sdl")]
+    pub fn draw(&self, y_pos: i32, naming: NoteNaming, canvas: &WindowCanvas) {
+        self.stave.draw(y_pos, canvas);
+
+        if let Some((key_signature, right)) = &self.last {
+            let (s, color) = match right {
+                true => ("right", Color::GREEN),
+                false => ("it was", Color::RED),
+            };
+            let y = (y_pos + self.stave.get_height() + 40) as i16;
+            canvas
+                .string(
+                    self.stave.x_pos as i16,
+                    y,
+                    &format!("{}: {}", s, get_keys_name(key_signature, naming)),
+                    color,
+                )
+                .unwrap();
+        }
+    }
+}
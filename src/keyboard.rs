@@ -0,0 +1,121 @@
+#[cfg(feature = "sdl")]
+use sdl2::gfx::primitives::DrawRenderer;
+#[cfg(feature = "sdl")]
+use sdl2::render::WindowCanvas;
+
+#[cfg(feature = "sdl")]
+use crate::graphics::Color;
+use crate::graphics::Point;
+use crate::music::Semitone;
+
+//C#, D#, F#, G#, A#
+pub fn is_black_key(s: Semitone) -> bool {
+    matches!(s.0 % 12, 1 | 3 | 6 | 8 | 10)
+}
+
+//a strip of piano keys from a white key to another one
+pub struct Keyboard {
+    pub pos: Point,
+    pub size: Point,
+    low: Semitone,
+    high: Semitone,
+}
+
+impl Keyboard {
+    //the range is widened to start and end on white keys
+    pub fn new(pos: Point, size: Point, low: Semitone, high: Semitone) -> Keyboard {
+        let (mut low, mut high) = (Semitone(low.0.min(high.0)), Semitone(low.0.max(high.0)));
+        if is_black_key(low) {
+            low.0 -= 1;
+        }
+        if is_black_key(high) {
+            high.0 += 1;
+        }
+        Keyboard {
+            pos,
+            size,
+            low,
+            high,
+        }
+    }
+
+    fn get_nb_white_keys(&self) -> i32 {
+        (self.low.0..=self.high.0)
+            .filter(|s| !is_black_key(Semitone(*s)))
+            .count() as i32
+    }
+
+    fn get_white_width(&self) -> i32 {
+        self.size.x / self.get_nb_white_keys()
+    }
+
+    //x of the left side of a white key, or of the middle of a black key
+    fn get_key_x(&self, s: Semitone) -> i32 {
+        let nb_whites_before = (self.low.0..s.0)
+            .filter(|s| !is_black_key(Semitone(*s)))
+            .count() as i32;
+        self.pos.x + nb_whites_before * self.get_white_width()
+    }
+
+    //the key under the point, black keys are above the white ones
+    pub fn get_semitone_at(&self, x: i32, y: i32) -> Option<Semitone> {
+        if y < self.pos.y || y > self.pos.y + self.size.y {
+            return None;
+        }
+        let white_width = self.get_white_width();
+        let black_width = white_width * 3 / 5;
+        let keys = (self.low.0..=self.high.0).map(Semitone);
+        if y <= self.pos.y + self.size.y * 3 / 5 {
+            let black = keys
+                .clone()
+                .filter(|s| is_black_key(*s))
+                .find(|s| (x - self.get_key_x(*s)).abs() <= black_width / 2);
+            if black.is_some() {
+                return black;
+            }
+        }
+        keys.filter(|s| !is_black_key(*s)).find(|s| {
+            let x_key = self.get_key_x(*s);
+            x >= x_key && x < x_key + white_width
+        })
+    }
+
+    //get_color gives the color of a key, None keeps it white or black
+    #[cfg(feature = "sdl")]
+    pub fn draw(&self, canvas: &WindowCanvas, get_color: impl Fn(Semitone) -> Option<Color>) {
+        let white_width = self.get_white_width();
+        let black_width = white_width * 3 / 5;
+        let top = self.pos.y as i16;
+
+        for s in (self.low.0..=self.high.0).map(Semitone) {
+            if is_black_key(s) {
+                continue;
+            }
+            let x = self.get_key_x(s) as i16;
+            let color = get_color(s).unwrap_or(Color::WHITE);
+            let bottom = (self.pos.y + self.size.y) as i16;
+            canvas
+                .box_(x, top, x + white_width as i16, bottom, color)
+                .unwrap();
+            canvas
+                .rectangle(x, top, x + white_width as i16, bottom, Color::BLACK)
+                .unwrap();
+        }
+
+        //black keys are drawn over the white ones
+        for s in (self.low.0..=self.high.0).map(Semitone) {
+            if !is_black_key(s) {
+                continue;
+            }
+            let x = (self.get_key_x(s) - black_width / 2) as i16;
+            let color = get_color(s).unwrap_or(Color::BLACK);
+            let bottom = (self.pos.y + self.size.y * 3 / 5) as i16;
+            canvas
+                .box_(x, top, x + black_width as i16, bottom, color)
+                .unwrap();
+            canvas
+                .rectangle(x, top, x + black_width as i16, bottom, Color::BLACK)
+                .unwrap();
+        }
+    }
+}
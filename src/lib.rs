@@ -0,0 +1,17 @@
+//the music model, the exercises and the game logic, without window nor midi port
+//drawing on an sdl2 canvas needs the feature sdl, the game window is the binary
+
+pub mod export;
+pub mod game;
+pub mod generator;
+pub mod graphics;
+pub mod history;
+pub mod key_drill;
+pub mod keyboard;
+pub mod midi;
+pub mod music;
+pub mod repetition;
+#[cfg(feature = "sdl")]
+pub mod review;
+pub mod stats;
+pub mod stave;
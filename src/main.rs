@@ -1,25 +1,122 @@
 extern crate sdl2;
-use sdl2::{event::Event, keyboard::Scancode};
+use sdl2::{event::Event, keyboard::{Mod, Scancode}};
 use sdl2::gfx::framerate::FPSManager;
 use sdl2::gfx::primitives::DrawRenderer;
 use sdl2::pixels::Color;
 
 use midir::{Ignore, MidiInput};
 
+use std::path::Path;
 use std::sync::{Arc, Mutex};
 
-mod game;
-mod music;
-use crate::game::Game;
+mod qwerty;
+use train_piano::export::{export, export_to_file, ExportFormat};
+use train_piano::game::{Game, PedalMode};
+use train_piano::generator::{ClefPolicy, KeySignaturePolicy};
+use train_piano::history::{History, Session};
+use crate::qwerty::Qwerty;
+use train_piano::music::{
+    Accidental, AccidentalWeights, Clef, KeySignature, KeySignatureAccidental, KeySignatureWeights, Interval, Key, Mode, NoteNaming,
+    NoteRange, Octave, Pitch, PitchWeights, Quality, parse_note,
+};
 
 
 
 const SCREEN_WIDTH: u32 = 800;
-const SCREEN_HEIGHT: u32 = 600;
+const SCREEN_HEIGHT: u32 = 720;
 
 
+//natural note with the octave numbers of scientific pitch notation, middle C is C4
+fn parse_natural_note(s: &str) -> Option<(Pitch, Octave)> {
+    match parse_note(s)? {
+        (pitch, None, octave) => Some((pitch, octave)),
+        _ => None,
+    }
+}
+
+//a letter with #, b, x or bb, as F#
+fn parse_tonic(s: &str) -> Option<(Pitch, Option<Accidental>)> {
+    let (pitch, accidental, _) = parse_note(&format!("{}4", s))?;
+    Some((pitch, accidental.filter(|a| *a != Accidental::Natural)))
+}
+
+//letter keys of the names quiz, H is B in german
+fn get_letter(sc: Scancode) -> Option<char> {
+    match sc {
+        Scancode::A => Some('A'),
+        Scancode::B => Some('B'),
+        Scancode::C => Some('C'),
+        Scancode::D => Some('D'),
+        Scancode::E => Some('E'),
+        Scancode::F => Some('F'),
+        Scancode::G => Some('G'),
+        Scancode::H => Some('H'),
+        _ => None,
+    }
+}
+
+//number keys of the intervals quiz, from a 2nd to an octave
+fn get_interval_number(sc: Scancode) -> Option<u8> {
+    match sc {
+        Scancode::Num2 | Scancode::Kp2 => Some(2),
+        Scancode::Num3 | Scancode::Kp3 => Some(3),
+        Scancode::Num4 | Scancode::Kp4 => Some(4),
+        Scancode::Num5 | Scancode::Kp5 => Some(5),
+        Scancode::Num6 | Scancode::Kp6 => Some(6),
+        Scancode::Num7 | Scancode::Kp7 => Some(7),
+        Scancode::Num8 | Scancode::Kp8 => Some(8),
+        _ => None,
+    }
+}
+
+//N weights separated by commas
+fn parse_weights<const N: usize>(s: &str) -> Option<[u32; N]> {
+    let weights: Vec<u32> = s.split(',').map(|w| w.trim().parse().ok()).collect::<Option<_>>()?;
+    weights.try_into().ok()
+}
+
+//the problems of the history file go to stderr, the export can be printed on stdout
+fn load_history() -> History {
+    let (history, problems) = History::load();
+    for p in problems.iter() {
+        eprintln!("{}", p);
+    }
+    history
+}
+
 fn main() -> Result<(), String> {
 
+    //train_piano export csv|json [FILE] [--naming english|solfege|german], the history is written to FILE or printed
+    let args: Vec<String> = std::env::args().collect();
+    if args.get(1).map(|a| a.as_str()) == Some("export") {
+        let format = args
+            .get(2)
+            .and_then(|f| ExportFormat::from_name(f))
+            .ok_or("export expects csv or json")?;
+        let mut path = None;
+        let mut naming = NoteNaming::English;
+        let mut i_arg = 3;
+        while i_arg < args.len() {
+            if args[i_arg] == "--naming" {
+                i_arg += 1;
+                naming = args
+                    .get(i_arg)
+                    .and_then(|n| NoteNaming::from_name(n))
+                    .ok_or("--naming expects english, solfege or german")?;
+            } else {
+                path = Some(Path::new(&args[i_arg]));
+            }
+            i_arg += 1;
+        }
+        let history = load_history();
+        let sessions: Vec<&Session> = history.sessions.iter().collect();
+        match path {
+            Some(path) => export_to_file(&sessions, format, naming, path)?,
+            None => print!("{}", export(&sessions, format, naming)),
+        }
+        return Ok(());
+    }
+
     let mut _conn_in;
     let mut midi_in = Some(MidiInput::new("midir reading input").unwrap());
     midi_in.as_mut().unwrap().ignore(Ignore::None);
@@ -29,12 +126,161 @@ fn main() -> Result<(), String> {
     let btn_pos_y = 70;
     let btn_size_y = 30;
 
-    let game = Arc::new(Mutex::new(Game::new(SCREEN_WIDTH)));
+    let game = Arc::new(Mutex::new(Game::new(SCREEN_WIDTH, SCREEN_HEIGHT, load_history())));
+    //played with the computer keyboard instead of a midi port
+    let mut qwerty: Option<Qwerty> = None;
+    //the staves are shown once the midi port is chosen
+    let mut started = false;
 
-    let callback = |_, message: &[u8], g: &mut Arc<Mutex<Game>>| {
-        if message.len() == 3 {
-            g.lock().unwrap().parse_midi_message(message);
+    //options
+    let mut i_arg = 1;
+    while i_arg < args.len() {
+        match args[i_arg].as_str() {
+            //midi channel from 1 to 16
+            "--channel" => {
+                i_arg += 1;
+                let channel = args
+                    .get(i_arg)
+                    .and_then(|c| c.parse::<u8>().ok())
+                    .filter(|c| (1..=16).contains(c))
+                    .ok_or("--channel expects a number from 1 to 16")?;
+                game.lock().unwrap().set_midi_channel(Some(channel - 1));
+            }
+            //keys | sound
+            "--pedal-mode" => {
+                i_arg += 1;
+                let pedal_mode = match args.get(i_arg).map(|m| m.as_str()) {
+                    Some("keys") => PedalMode::Keys,
+                    Some("sound") => PedalMode::Sound,
+                    _ => return Err("--pedal-mode expects keys or sound".to_string()),
+                };
+                game.lock().unwrap().set_pedal_mode(pedal_mode);
+            }
+            //quarter notes per minute in timed mode
+            "--bpm" => {
+                i_arg += 1;
+                let bpm = args
+                    .get(i_arg)
+                    .and_then(|b| b.parse::<u32>().ok())
+                    .ok_or("--bpm expects a number")?;
+                game.lock().unwrap().set_bpm(bpm);
+            }
+            //random | sol | fa | grand
+            "--clef" => {
+                i_arg += 1;
+                let clef_policy = match args.get(i_arg).map(|c| c.as_str()) {
+                    Some("random") => ClefPolicy::Random,
+                    Some("sol") => ClefPolicy::Fixed(Clef::Sol),
+                    Some("fa") => ClefPolicy::Fixed(Clef::Fa),
+                    Some("grand") => ClefPolicy::Grand,
+                    _ => return Err("--clef expects random, sol, fa or grand".to_string()),
+                };
+                game.lock().unwrap().set_clef_policy(clef_policy);
+            }
+            //random | 0 | 1# .. 7# | 1b .. 7b | a key as D-minor, F#-major, Bb-harmonic-minor, E-dorian
+            "--key" => {
+                i_arg += 1;
+                let key = args.get(i_arg).ok_or("--key expects random, 0, N#, Nb or a key as D-minor")?;
+                let key_signature_policy = match key.as_str() {
+                    "random" => KeySignaturePolicy::Random,
+                    "0" => KeySignaturePolicy::Fixed(KeySignature::new(KeySignatureAccidental::Sharp, 0)),
+                    k if k.contains('-') => {
+                        let (tonic, mode) = k.split_once('-').unwrap();
+                        let tonic = parse_tonic(tonic).ok_or("--key expects a tonic as C, F# or Bb")?;
+                        let mode = Mode::from_name(mode).ok_or(
+                            "--key expects major, minor, harmonic-minor, melodic-minor, dorian, phrygian, lydian, mixolydian or locrian",
+                        )?;
+                        KeySignaturePolicy::Key(Key::new(tonic.0, tonic.1, mode)?)
+                    }
+                    k => {
+                        let (nb, accidental) = match (k.strip_suffix('#'), k.strip_suffix('b')) {
+                            (Some(nb), _) => (nb, KeySignatureAccidental::Sharp),
+                            (_, Some(nb)) => (nb, KeySignatureAccidental::Flat),
+                            _ => return Err("--key expects random, 0, N# or Nb".to_string()),
+                        };
+                        let nb = nb
+                            .parse::<u8>()
+                            .ok()
+                            .filter(|n| (1..=7).contains(n))
+                            .ok_or("--key expects from 1 to 7 sharps or flats")?;
+                        KeySignaturePolicy::Fixed(KeySignature::new(accidental, nb))
+                    }
+                };
+                game.lock().unwrap().set_key_signature_policy(key_signature_policy);
+            }
+            //lowest and highest notes, as C4 A5
+            "--sol-range" | "--fa-range" => {
+                let clef = if args[i_arg] == "--sol-range" { Clef::Sol } else { Clef::Fa };
+                let low = args.get(i_arg + 1).and_then(|n| parse_natural_note(n));
+                let high = args.get(i_arg + 2).and_then(|n| parse_natural_note(n));
+                i_arg += 2;
+                match (low, high) {
+                    (Some(low), Some(high)) => {
+                        game.lock().unwrap().set_range(clef, NoteRange::new(low, high)?);
+                    }
+                    _ => return Err("--sol-range and --fa-range expect two notes as C4 A5".to_string()),
+                }
+            }
+            //ledger lines above and below the stave in the ledger lines drill
+            "--ledger-lines" => {
+                i_arg += 1;
+                let ledger_lines = args
+                    .get(i_arg)
+                    .and_then(|n| n.parse::<u8>().ok())
+                    .filter(|n| (1..=5).contains(n))
+                    .ok_or("--ledger-lines expects a number from 1 to 5")?;
+                game.lock().unwrap().set_ledger_lines(ledger_lines);
+            }
+            //weights of C,D,E,F,G,A,B as 1,1,1,1,1,1,1
+            "--pitch-weights" => {
+                i_arg += 1;
+                let weights = args
+                    .get(i_arg)
+                    .and_then(|w| parse_weights::<7>(w))
+                    .ok_or("--pitch-weights expects 7 weights as 1,1,1,1,1,1,1 for C to B")?;
+                game.lock().unwrap().set_pitch_weights(PitchWeights::new(weights)?);
+            }
+            //weights of no accidental, sharp, flat and natural as 3,1,1,1
+            "--accidental-weights" => {
+                i_arg += 1;
+                let [none, sharp, flat, natural] = args
+                    .get(i_arg)
+                    .and_then(|w| parse_weights::<4>(w))
+                    .ok_or("--accidental-weights expects 4 weights as none,sharp,flat,natural")?;
+                game.lock().unwrap().set_accidental_weights(AccidentalWeights::new(none, sharp, flat, natural)?);
+            }
+            //weights of 0 to 7 sharps or flats as 20,1,1,1,1,1,0,0
+            "--key-weights" => {
+                i_arg += 1;
+                let weights = args
+                    .get(i_arg)
+                    .and_then(|w| parse_weights::<8>(w))
+                    .ok_or("--key-weights expects 8 weights for 0 to 7 sharps or flats")?;
+                game.lock().unwrap().set_key_signature_weights(KeySignatureWeights::new(weights)?);
+            }
+            //english | solfege | german
+            "--naming" => {
+                i_arg += 1;
+                let naming = args
+                    .get(i_arg)
+                    .and_then(|n| NoteNaming::from_name(n))
+                    .ok_or("--naming expects english, solfege or german")?;
+                game.lock().unwrap().set_naming(naming);
+            }
+            //every note as likely, whatever the mistakes
+            "--no-adaptive" => game.lock().unwrap().toggle_adaptive(),
+            //no midi port, the computer keyboard is the piano
+            "--qwerty" => {
+                midi_in = None;
+                qwerty = Some(Qwerty::new());
+            }
+            a => return Err(format!("unknown option: {}", a)),
         }
+        i_arg += 1;
+    }
+
+    let callback = |_, message: &[u8], g: &mut Arc<Mutex<Game>>| {
+        g.lock().unwrap().parse_midi_message(message);
     };
 
         
@@ -57,6 +303,8 @@ fn main() -> Result<(), String> {
     'main: loop {
         //events
         for event in events.poll_iter() {
+            let quiz = midi_in.is_none() && game.lock().unwrap().is_quiz();
+            let naming = game.lock().unwrap().get_naming();
             match event {
                 Event::Quit { .. } => break 'main,
 
@@ -69,8 +317,177 @@ fn main() -> Result<(), String> {
                 //     }
                 // }
 
+                Event::KeyDown {
+                    scancode: Some(Scancode::F1),
+                    ..
+                } => {
+                    if midi_in.is_none() {
+                        game.lock().unwrap().next_exercise();
+                    }
+                }
+
+                Event::KeyDown {
+                    scancode: Some(Scancode::F2),
+                    ..
+                } => {
+                    if midi_in.is_none() {
+                        game.lock().unwrap().next_clef_policy();
+                    }
+                }
+
+                Event::KeyDown {
+                    scancode: Some(Scancode::F3),
+                    ..
+                } => {
+                    if midi_in.is_none() {
+                        game.lock().unwrap().toggle_rhythm();
+                    }
+                }
+
+                Event::KeyDown {
+                    scancode: Some(Scancode::F4),
+                    ..
+                } => {
+                    if midi_in.is_none() {
+                        game.lock().unwrap().toggle_timed();
+                    }
+                }
+
+                Event::KeyDown {
+                    scancode: Some(Scancode::F5),
+                    ..
+                } => {
+                    if midi_in.is_none() {
+                        game.lock().unwrap().toggle_adaptive();
+                    }
+                }
+
+                Event::KeyDown {
+                    scancode: Some(Scancode::F6),
+                    ..
+                } => {
+                    if midi_in.is_none() {
+                        game.lock().unwrap().toggle_stats();
+                    }
+                }
+
+                Event::KeyDown {
+                    scancode: Some(Scancode::F7),
+                    ..
+                } => {
+                    if midi_in.is_none() {
+                        game.lock().unwrap().toggle_review();
+                    }
+                }
+
+                Event::KeyDown {
+                    scancode: Some(Scancode::F8),
+                    ..
+                } => {
+                    if midi_in.is_none() {
+                        game.lock().unwrap().export();
+                    }
+                }
+
+                Event::KeyDown {
+                    scancode: Some(Scancode::F9),
+                    ..
+                } => {
+                    if midi_in.is_none() {
+                        game.lock().unwrap().next_naming();
+                    }
+                }
+
+                Event::KeyDown {
+                    scancode: Some(sc @ (Scancode::Up | Scancode::Down)),
+                    ..
+                } => {
+                    if midi_in.is_none() {
+                        let mut g = game.lock().unwrap();
+                        let bpm = g.get_bpm();
+                        match sc {
+                            Scancode::Up => g.set_bpm(bpm + 5),
+                            _ => g.set_bpm(bpm.saturating_sub(5)),
+                        }
+                    }
+                }
+
+                //the letters name the notes in the quiz
+                Event::KeyDown {
+                    scancode: Some(sc),
+                    keymod,
+                    repeat: false,
+                    ..
+                } if quiz && get_letter(sc).and_then(|l| naming.get_letter_note(l, Accidental::Natural)).is_some() => {
+                    //alt doubles the sharp or the flat
+                    let double = keymod.intersects(Mod::LALTMOD | Mod::RALTMOD);
+                    let accidental = if keymod.intersects(Mod::LSHIFTMOD | Mod::RSHIFTMOD) {
+                        if double { Accidental::DoubleSharp } else { Accidental::Sharp }
+                    } else if keymod.intersects(Mod::LCTRLMOD | Mod::RCTRLMOD) {
+                        if double { Accidental::DoubleFlat } else { Accidental::Flat }
+                    } else {
+                        Accidental::Natural
+                    };
+                    let (pitch, accidental) = naming.get_letter_note(get_letter(sc).unwrap(), accidental).unwrap();
+                    game.lock().unwrap().name_note(pitch, accidental);
+                }
+
+                //the numbers name the intervals in the quiz, a minor 5th is not an interval and is ignored
+                Event::KeyDown {
+                    scancode: Some(sc),
+                    keymod,
+                    repeat: false,
+                    ..
+                } if quiz && get_interval_number(sc).is_some() => {
+                    let number = get_interval_number(sc).unwrap();
+                    let interval = if keymod.intersects(Mod::LSHIFTMOD | Mod::RSHIFTMOD) {
+                        Interval::new(number, Quality::Minor)
+                    } else if keymod.intersects(Mod::LCTRLMOD | Mod::RCTRLMOD) {
+                        Interval::new(number, Quality::Diminished)
+                    } else if keymod.intersects(Mod::LALTMOD | Mod::RALTMOD) {
+                        Interval::new(number, Quality::Augmented)
+                    } else {
+                        Interval::new(number, Quality::Major).or(Interval::new(number, Quality::Perfect))
+                    };
+                    if let Ok(i) = interval {
+                        game.lock().unwrap().name_interval(i);
+                    }
+                }
+
+                //the computer keyboard as a piano
+                Event::KeyDown {
+                    scancode: Some(sc),
+                    repeat,
+                    ..
+                } if qwerty.is_some() && Qwerty::is_mapped(sc) => {
+                    if !repeat {
+                        if let Some(s) = qwerty.as_mut().unwrap().press(sc) {
+                            game.lock().unwrap().pressed_semitone(&s);
+                        }
+                    }
+                }
+
+                Event::KeyUp {
+                    scancode: Some(sc),
+                    ..
+                } if qwerty.is_some() => {
+                    if let Some(s) = qwerty.as_mut().unwrap().release(sc) {
+                        game.lock().unwrap().released_semitone(&s);
+                    }
+                }
+
+                Event::KeyDown {
+                    scancode: Some(Scancode::Return),
+                    ..
+                } => {
+                    if midi_in.is_some() {
+                        midi_in = None;
+                        qwerty = Some(Qwerty::new());
+                    }
+                }
+
                 Event::KeyDown {  scancode, .. } => {
-                    if let Some(sc) = scancode {
+                    if let (Some(sc), true) = (scancode, midi_in.is_some()) {
                         let index = match sc {
                             Scancode::Num0 | Scancode::Kp0 => Some(0),
                             Scancode::Num1 | Scancode::Kp1 => Some(1),
@@ -94,8 +511,13 @@ fn main() -> Result<(), String> {
                     }
                 }
 
-                Event::MouseButtonDown {  y, .. } => {
-                    if midi_in.is_some() {
+                Event::MouseButtonUp { .. } => game.lock().unwrap().unclick(),
+
+                Event::MouseButtonDown {  x, y, .. } => {
+                    if midi_in.is_none() {
+                        game.lock().unwrap().click(x, y);
+                    }
+                    else {
                         // println!("mouse btn down at ({},{})", x, y);
                         let i = (y-btn_pos_y)/btn_size_y;
                         
@@ -111,6 +533,11 @@ fn main() -> Result<(), String> {
         }
 
         //logic
+        if !started && midi_in.is_none() {
+            game.lock().unwrap().start();
+            started = true;
+        }
+        game.lock().unwrap().update();
 
         //render
         canvas.set_draw_color(Color::RGB(255, 255, 255));
@@ -129,9 +556,13 @@ fn main() -> Result<(), String> {
                     canvas.string(20, (btn_pos_y+i as i32*btn_size_y) as i16, &s, Color::RGB(0, 0, 0)).unwrap();
                 }
             }
+            canvas.string(20, 500, "Press Enter to play with the computer keyboard", Color::RGB(0, 0, 0)).unwrap();
         }
         else{
             game.lock().unwrap().draw(&canvas);
+            if let Some(q) = &qwerty {
+                canvas.string(300, 25, &format!("{} z/x", q.get_octave_name(game.lock().unwrap().get_naming())), Color::RGB(0, 0, 0)).unwrap();
+            }
         }
         
         // canvas.string(20, 400, &fps_manager.get_frame_count().to_string(), Color::RGB(0, 0, 0)).unwrap();
@@ -141,6 +572,8 @@ fn main() -> Result<(), String> {
         fps_manager.delay();
     }
 
+    game.lock().unwrap().save_session();
+
     Ok(())
 }
 
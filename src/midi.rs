@@ -0,0 +1,277 @@
+//decoding of the raw bytes given by midir into channel messages
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum MidiMessage {
+    NoteOn { channel: u8, key: u8, velocity: u8 },
+    NoteOff { channel: u8, key: u8, velocity: u8 },
+    PolyAftertouch { channel: u8, key: u8, pressure: u8 },
+    ControlChange { channel: u8, controller: u8, value: u8 },
+    ProgramChange { channel: u8, program: u8 },
+    ChannelAftertouch { channel: u8, pressure: u8 },
+    //centered on 0, from -8192 to 8191
+    PitchBend { channel: u8, value: i16 },
+}
+
+impl MidiMessage {
+    pub fn get_channel(&self) -> u8 {
+        match *self {
+            Self::NoteOn { channel, .. }
+            | Self::NoteOff { channel, .. }
+            | Self::PolyAftertouch { channel, .. }
+            | Self::ControlChange { channel, .. }
+            | Self::ProgramChange { channel, .. }
+            | Self::ChannelAftertouch { channel, .. }
+            | Self::PitchBend { channel, .. } => channel,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Pedal {
+    Sustain,
+    Sostenuto,
+    Soft,
+}
+
+impl Pedal {
+    pub fn from_controller(controller: u8) -> Option<Pedal> {
+        match controller {
+            64 => Some(Pedal::Sustain),
+            66 => Some(Pedal::Sostenuto),
+            67 => Some(Pedal::Soft),
+            _ => None,
+        }
+    }
+}
+
+//pedals are on/off switches, down from the middle of the range
+pub fn is_pedal_down(value: u8) -> bool {
+    value >= 64
+}
+
+//number of data bytes following a channel status byte
+fn get_data_len(status: u8) -> usize {
+    match status & 0xF0 {
+        0xC0 | 0xD0 => 1,
+        _ => 2,
+    }
+}
+
+//number of data bytes following a system common status byte
+//None for sysex which ends with 0xF7
+fn get_system_data_len(status: u8) -> Option<usize> {
+    match status {
+        0xF0 => None,
+        0xF1 | 0xF3 => Some(1),
+        0xF2 => Some(2),
+        _ => Some(0),
+    }
+}
+
+#[derive(Default)]
+pub struct MidiDecoder {
+    running_status: Option<u8>,
+    //None means every channel is listened (omni)
+    //channels are from 0 to 15
+    channel: Option<u8>,
+}
+
+impl MidiDecoder {
+    pub fn new() -> MidiDecoder {
+        MidiDecoder::default()
+    }
+
+    pub fn set_channel(&mut self, channel: Option<u8>) {
+        self.channel = channel.map(|c| c.clamp(0, 15));
+    }
+
+    //a buffer can hold several messages, and messages can omit their status byte (running status)
+    pub fn decode(&mut self, bytes: &[u8]) -> Vec<MidiMessage> {
+        //real time messages can be anywhere, even inside another message, and do not touch running status
+        let bytes: Vec<u8> = bytes.iter().copied().filter(|b| *b < 0xF8).collect();
+        let mut messages = Vec::new();
+        let mut i = 0;
+
+        while i < bytes.len() {
+            let b = bytes[i];
+
+            //system common messages cancel running status
+            if b >= 0xF0 {
+                self.running_status = None;
+                i += 1;
+                match get_system_data_len(b) {
+                    //a status byte in place of a data byte starts the next message
+                    Some(len) => {
+                        i += bytes[i..]
+                            .iter()
+                            .take(len)
+                            .take_while(|d| **d < 0x80)
+                            .count()
+                    }
+                    None => {
+                        while i < bytes.len() && bytes[i] != 0xF7 {
+                            i += 1;
+                        }
+                        i += 1;
+                    }
+                }
+                continue;
+            }
+
+            let status;
+            if b >= 0x80 {
+                status = b;
+                self.running_status = Some(b);
+                i += 1;
+            } else if let Some(s) = self.running_status {
+                status = s;
+            } else {
+                //data byte without any status to refer to
+                i += 1;
+                continue;
+            }
+
+            let len = get_data_len(status);
+            let data = &bytes[i..(i + len).min(bytes.len())];
+            if data.len() < len || data.iter().any(|d| *d >= 0x80) {
+                //truncated message, the next status byte (if any) is parsed again
+                i += data.iter().take_while(|d| **d < 0x80).count();
+                continue;
+            }
+            i += len;
+
+            if let Some(m) = Self::to_message(status, data) {
+                if self.channel.is_none() || self.channel == Some(m.get_channel()) {
+                    messages.push(m);
+                }
+            }
+        }
+
+        messages
+    }
+
+    fn to_message(status: u8, data: &[u8]) -> Option<MidiMessage> {
+        let channel = status & 0x0F;
+        let m = match status & 0xF0 {
+            0x80 => MidiMessage::NoteOff {
+                channel,
+                key: data[0],
+                velocity: data[1],
+            },
+            //a note on with velocity 0 is a note off
+            0x90 if data[1] == 0 => MidiMessage::NoteOff {
+                channel,
+                key: data[0],
+                velocity: 0,
+            },
+            0x90 => MidiMessage::NoteOn {
+                channel,
+                key: data[0],
+                velocity: data[1],
+            },
+            0xA0 => MidiMessage::PolyAftertouch {
+                channel,
+                key: data[0],
+                pressure: data[1],
+            },
+            0xB0 => MidiMessage::ControlChange {
+                channel,
+                controller: data[0],
+                value: data[1],
+            },
+            0xC0 => MidiMessage::ProgramChange {
+                channel,
+                program: data[0],
+            },
+            0xD0 => MidiMessage::ChannelAftertouch {
+                channel,
+                pressure: data[0],
+            },
+            0xE0 => MidiMessage::PitchBend {
+                channel,
+                value: ((data[1] as i16) << 7 | data[0] as i16) - 8192,
+            },
+            _ => return None,
+        };
+        Some(m)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn note_on(channel: u8, key: u8, velocity: u8) -> MidiMessage {
+        MidiMessage::NoteOn {
+            channel,
+            key,
+            velocity,
+        }
+    }
+
+    #[test]
+    fn running_status() {
+        let mut d = MidiDecoder::new();
+        assert_eq!(
+            d.decode(&[0x90, 60, 100, 64, 90]),
+            vec![note_on(0, 60, 100), note_on(0, 64, 90)]
+        );
+        //the status is kept from one buffer to the next
+        assert_eq!(d.decode(&[67, 80]), vec![note_on(0, 67, 80)]);
+    }
+
+    #[test]
+    fn note_on_without_velocity_is_note_off() {
+        let mut d = MidiDecoder::new();
+        assert_eq!(
+            d.decode(&[0x93, 60, 0]),
+            vec![MidiMessage::NoteOff {
+                channel: 3,
+                key: 60,
+                velocity: 0
+            }]
+        );
+    }
+
+    #[test]
+    fn channel_filter() {
+        let mut d = MidiDecoder::new();
+        d.set_channel(Some(1));
+        assert_eq!(
+            d.decode(&[0x90, 60, 100, 0x91, 62, 100]),
+            vec![note_on(1, 62, 100)]
+        );
+        d.set_channel(None);
+        assert_eq!(d.decode(&[0x90, 60, 100]), vec![note_on(0, 60, 100)]);
+    }
+
+    #[test]
+    fn real_time_bytes() {
+        let mut d = MidiDecoder::new();
+        assert_eq!(
+            d.decode(&[0xF8, 0x90, 60, 0xFE, 100, 0xF8, 62, 100]),
+            vec![note_on(0, 60, 100), note_on(0, 62, 100)]
+        );
+    }
+
+    #[test]
+    fn system_common_without_data() {
+        let mut d = MidiDecoder::new();
+        assert_eq!(d.decode(&[0xF2, 0x90, 60, 100]), vec![note_on(0, 60, 100)]);
+        assert_eq!(d.decode(&[0xF1, 0x90, 60, 100]), vec![note_on(0, 60, 100)]);
+        //the song position is skipped and running status is cancelled
+        assert_eq!(
+            d.decode(&[0x90, 60, 100, 0xF2, 1, 2, 62, 100]),
+            vec![note_on(0, 60, 100)]
+        );
+    }
+
+    #[test]
+    fn sysex() {
+        let mut d = MidiDecoder::new();
+        assert_eq!(
+            d.decode(&[0xF0, 0x7E, 1, 2, 0xF7, 0x90, 60, 100]),
+            vec![note_on(0, 60, 100)]
+        );
+    }
+}
@@ -1,10 +1,12 @@
 use std::fmt;
 
 use rand::{
-    distributions::{Distribution, Standard},
+    distributions::{Distribution, Standard, WeightedIndex},
     Rng,
 };
 
+use crate::stave::Note;
+
 //A-1 on my piano is 21
 //C0 = 24
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
@@ -30,23 +32,139 @@ pub enum Accidental {
     Sharp,
     Flat,
     Natural,
+    DoubleSharp,
+    DoubleFlat,
 }
 
-#[derive(Debug, PartialEq, Clone, Copy)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
 pub enum Clef {
     Sol,
     Fa,
 }
 
+//sustain pedal marks written under the stave
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum PedalMark {
+    //Ped.
+    Press,
+    //*
+    Release,
+    //*Ped. release then press again
+    Change,
+}
+
+//how the notes are named
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum NoteNaming {
+    //C D E F G A B, C# Db
+    English,
+    //fixed do: Do Ré Mi Fa Sol La Si, Do# Réb
+    Solfege,
+    //C D E F G A H, B is B flat, Cis Des
+    German,
+}
+
+//lowest and highest natural notes of a range, both included
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct NoteRange {
+    //in steps, see get_step
+    low: i32,
+    high: i32,
+}
+
+//how often each value comes in the random staves, a value of weight 0 never comes
+//weights are capped so their sums and the weights of the adaptive notes fit in u32
+pub const MAX_WEIGHT: u32 = 100;
+
+//weights of C, D, E, F, G, A, B
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct PitchWeights([u32; 7]);
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct AccidentalWeights {
+    //no accidental written
+    none: u32,
+    sharp: u32,
+    flat: u32,
+    natural: u32,
+}
+
+//weights of 0 to 7 sharps or flats, sharps and flats are as likely
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct KeySignatureWeights([u32; 8]);
+
+//lengths are counted in ticks, a whole note is 32 ticks so a dotted sixteenth is still a whole number
+pub const TICKS_WHOLE: u32 = 32;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum NoteValue {
+    Whole,
+    Half,
+    Quarter,
+    Eighth,
+    Sixteenth,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct NoteLength {
+    pub value: NoteValue,
+    pub dotted: bool,
+}
+
+//beats per measure and the value of one beat, 6/8 is TimeSignature(6, NoteValue::Eighth)
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct TimeSignature(pub u8, pub NoteValue);
+
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub enum KeySignatureAccidental {
     Sharp,
     Flat,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, PartialEq, Clone, Copy)]
 pub struct KeySignature(pub KeySignatureAccidental, u8);
 
+//the minors and the church modes, ionian is major and aeolian natural minor
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum Mode {
+    Major,
+    NaturalMinor,
+    //natural minor with a raised 7th
+    HarmonicMinor,
+    //ascending form, with a raised 6th and 7th
+    MelodicMinor,
+    Dorian,
+    Phrygian,
+    Lydian,
+    Mixolydian,
+    Locrian,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum Quality {
+    Diminished,
+    Minor,
+    Perfect,
+    Major,
+    Augmented,
+}
+
+//from a 2nd to an octave, a major 3rd is Interval { number: 3, quality: Major }
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct Interval {
+    number: u8,
+    quality: Quality,
+}
+
+//a tonic and a mode, C# harmonic minor
+//only keys written with a key signature of at most 7 accidentals
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct Key {
+    tonic: Pitch,
+    accidental: Option<Accidental>,
+    mode: Mode,
+}
+
 pub const ORDER_SIGNATURE_SHARP: [Pitch; 7] = [
     Pitch::F,
     Pitch::C,
@@ -67,15 +185,80 @@ pub const ORDER_SIGNATURE_FLAT: [Pitch; 7] = [
 ];
 
 impl Accidental {
+    //from -2 for a double flat to 2 for a double sharp
+    pub fn from_semitone_offset(offset: i32) -> Option<Accidental> {
+        match offset {
+            -2 => Some(Self::DoubleFlat),
+            -1 => Some(Self::Flat),
+            0 => Some(Self::Natural),
+            1 => Some(Self::Sharp),
+            2 => Some(Self::DoubleSharp),
+            _ => None,
+        }
+    }
+
     pub fn get_semitone_offset(&self) -> i8 {
         match self {
             Self::Sharp => 1,
             Self::Flat => -1,
             Self::Natural => 0,
+            Self::DoubleSharp => 2,
+            Self::DoubleFlat => -2,
         }
     }
 }
 
+impl NoteValue {
+    pub fn get_ticks(&self) -> u32 {
+        match self {
+            Self::Whole => TICKS_WHOLE,
+            Self::Half => TICKS_WHOLE / 2,
+            Self::Quarter => TICKS_WHOLE / 4,
+            Self::Eighth => TICKS_WHOLE / 8,
+            Self::Sixteenth => TICKS_WHOLE / 16,
+        }
+    }
+
+    //number written at the bottom of a time signature
+    pub fn get_denominator(&self) -> u32 {
+        TICKS_WHOLE / self.get_ticks()
+    }
+
+    //number of flags (or beams) on the stem
+    pub fn get_nb_flags(&self) -> u8 {
+        match self {
+            Self::Eighth => 1,
+            Self::Sixteenth => 2,
+            _ => 0,
+        }
+    }
+}
+
+impl NoteLength {
+    pub fn new(value: NoteValue, dotted: bool) -> NoteLength {
+        NoteLength { value, dotted }
+    }
+
+    pub fn get_ticks(&self) -> u32 {
+        match self.dotted {
+            true => self.value.get_ticks() * 3 / 2,
+            false => self.value.get_ticks(),
+        }
+    }
+}
+
+impl TimeSignature {
+    pub fn get_measure_ticks(&self) -> u32 {
+        self.0 as u32 * self.1.get_ticks()
+    }
+}
+
+impl fmt::Display for TimeSignature {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}/{}", self.0, self.1.get_denominator())
+    }
+}
+
 impl KeySignature {
     pub fn new(accidental: KeySignatureAccidental, nb: u8) -> KeySignature {
         let nb = nb.clamp(0, 7);
@@ -123,9 +306,470 @@ impl KeySignature {
             KeySignatureAccidental::Flat => Accidental::Flat,
         }
     }
+
+    //each sharp is a fifth up from C, each flat a fifth down
+    pub fn get_major_tonic(&self) -> (Pitch, Option<Accidental>) {
+        let i = match self.0 {
+            KeySignatureAccidental::Sharp => self.1 as i32 * 4,
+            KeySignatureAccidental::Flat => self.1 as i32 * 3,
+        };
+        self.get_degree(i)
+    }
+
+    //a minor third under the major tonic
+    pub fn get_minor_tonic(&self) -> (Pitch, Option<Accidental>) {
+        let (pitch, _) = self.get_major_tonic();
+        self.get_degree(pitch.get_diatonic_index() + 5)
+    }
+
+    //the pitch with the accidental of the key signature
+    fn get_degree(&self, diatonic_index: i32) -> (Pitch, Option<Accidental>) {
+        let pitch = Pitch::from_diatonic_index(diatonic_index);
+        match self.is_pitch_inside(pitch) {
+            true => (pitch, Some(self.get_accidental())),
+            false => (pitch, None),
+        }
+    }
+}
+
+impl Mode {
+    pub fn from_name(name: &str) -> Option<Mode> {
+        match name {
+            "major" | "ionian" => Some(Self::Major),
+            "minor" | "aeolian" => Some(Self::NaturalMinor),
+            "harmonic-minor" => Some(Self::HarmonicMinor),
+            "melodic-minor" => Some(Self::MelodicMinor),
+            "dorian" => Some(Self::Dorian),
+            "phrygian" => Some(Self::Phrygian),
+            "lydian" => Some(Self::Lydian),
+            "mixolydian" => Some(Self::Mixolydian),
+            "locrian" => Some(Self::Locrian),
+            _ => None,
+        }
+    }
+
+    //semitones of the 7 degrees from the tonic
+    pub fn get_intervals(&self) -> [i32; 7] {
+        match self {
+            Self::Major => [0, 2, 4, 5, 7, 9, 11],
+            Self::NaturalMinor => [0, 2, 3, 5, 7, 8, 10],
+            Self::HarmonicMinor => [0, 2, 3, 5, 7, 8, 11],
+            Self::MelodicMinor => [0, 2, 3, 5, 7, 9, 11],
+            Self::Dorian => [0, 2, 3, 5, 7, 9, 10],
+            Self::Phrygian => [0, 1, 3, 5, 7, 8, 10],
+            Self::Lydian => [0, 2, 4, 6, 7, 9, 11],
+            Self::Mixolydian => [0, 2, 4, 5, 7, 9, 10],
+            Self::Locrian => [0, 1, 3, 5, 6, 8, 10],
+        }
+    }
+
+    //degree from 0 of the tonic in the major scale having the same key signature
+    fn get_major_degree(&self) -> i32 {
+        match self {
+            Self::Major => 0,
+            Self::Dorian => 1,
+            Self::Phrygian => 2,
+            Self::Lydian => 3,
+            Self::Mixolydian => 4,
+            Self::NaturalMinor | Self::HarmonicMinor | Self::MelodicMinor => 5,
+            Self::Locrian => 6,
+        }
+    }
+}
+
+impl fmt::Display for Mode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Major => write!(f, "major"),
+            Self::NaturalMinor => write!(f, "minor"),
+            Self::HarmonicMinor => write!(f, "harmonic minor"),
+            Self::MelodicMinor => write!(f, "melodic minor"),
+            m => write!(f, "{}", format!("{:?}", m).to_lowercase()),
+        }
+    }
+}
+
+//midi number of a written note, None out of 0 to 127
+//C0 is 24, the octave of B#3 is the one of B3 even if it is the semitone of C4
+pub fn get_semitone(
+    pitch: Pitch,
+    accidental: Option<Accidental>,
+    octave: Octave,
+) -> Option<Semitone> {
+    let offset = accidental
+        .map(|a| a.get_semitone_offset() as i32)
+        .unwrap_or(0);
+    let s = octave
+        .0
+        .checked_mul(12)?
+        .checked_add(24 + pitch.get_semitone_offset() as i32 + offset)?;
+    match (0..=127).contains(&s) {
+        true => Some(Semitone(s as u8)),
+        false => None,
+    }
+}
+
+//every way to write the semitone with at most a double sharp or flat, B#2 C3 Dbb3 for 60
+//a natural note has no accidental, there is none above the midi notes
+pub fn get_spellings(semitone: Semitone) -> Vec<(Pitch, Option<Accidental>, Octave)> {
+    let mut spellings = Vec::new();
+    if semitone.0 > 127 {
+        return spellings;
+    }
+    for i in 0..7 {
+        let pitch = Pitch::from_diatonic_index(i);
+        let offset = get_accidental_offset(pitch, semitone.0 as i32);
+        if offset.abs() <= 2 {
+            let natural = semitone.0 as i32 - offset - pitch.get_semitone_offset() as i32;
+            let accidental =
+                Accidental::from_semitone_offset(offset).filter(|a| *a != Accidental::Natural);
+            spellings.push((pitch, accidental, Octave((natural - 24).div_euclid(12))));
+        }
+    }
+    spellings
+}
+
+//as C#4, Bb2, Fx3, Ebb5 or Cn4 for a written natural
+//the octave numbers are the ones of scientific pitch notation, middle C is C4 and Octave(3)
+pub fn format_note(pitch: Pitch, accidental: Option<Accidental>, octave: Octave) -> String {
+    let accidental = match accidental {
+        Some(Accidental::Natural) => "n",
+        a => get_accidental_sign(a),
+    };
+    format!("{:?}{}{}", pitch, accidental, octave.0 + 1)
+}
+
+//the notes written by format_note, ## is a double sharp too, the letter can be lowercase
+pub fn parse_note(s: &str) -> Option<(Pitch, Option<Accidental>, Octave)> {
+    let mut chars = s.chars();
+    let pitch = match chars.next()?.to_ascii_uppercase() {
+        'A' => Pitch::A,
+        'B' => Pitch::B,
+        'C' => Pitch::C,
+        'D' => Pitch::D,
+        'E' => Pitch::E,
+        'F' => Pitch::F,
+        'G' => Pitch::G,
+        _ => return None,
+    };
+    let rest = chars.as_str();
+    let (accidental, octave) = [
+        ("##", Accidental::DoubleSharp),
+        ("x", Accidental::DoubleSharp),
+        ("bb", Accidental::DoubleFlat),
+        ("#", Accidental::Sharp),
+        ("b", Accidental::Flat),
+        ("n", Accidental::Natural),
+    ]
+    .iter()
+    .find_map(|(sign, a)| rest.strip_prefix(sign).map(|o| (Some(*a), o)))
+    .unwrap_or((None, rest));
+    let octave = octave.parse::<i32>().ok()?.checked_sub(1)?;
+    Some((pitch, accidental, Octave(octave)))
+}
+
+//from the natural pitch to the semitone, from -6 to 5
+fn get_accidental_offset(pitch: Pitch, semitone: i32) -> i32 {
+    (semitone - pitch.get_semitone_offset() as i32 + 6).rem_euclid(12) - 6
+}
+
+impl Key {
+    pub fn new(tonic: Pitch, accidental: Option<Accidental>, mode: Mode) -> Result<Key, String> {
+        let key = Key {
+            tonic,
+            accidental,
+            mode,
+        };
+        let fifths = key.get_fifths();
+        if !(-7..=7).contains(&fifths) {
+            return Err(format!("{} needs more than 7 sharps or flats", key));
+        }
+        Ok(key)
+    }
+
+    fn get_tonic_semitone(&self) -> i32 {
+        let offset = self
+            .accidental
+            .map(|a| a.get_semitone_offset())
+            .unwrap_or(0);
+        self.tonic.get_semitone_offset() as i32 + offset as i32
+    }
+
+    //sharps as positive, flats as negative, from the major key of the same key signature
+    fn get_fifths(&self) -> i32 {
+        let degree = self.mode.get_major_degree();
+        let major = Pitch::from_diatonic_index(self.tonic.get_diatonic_index() - degree);
+        let major_semitone =
+            self.get_tonic_semitone() - Mode::Major.get_intervals()[degree as usize];
+        //C G D A E B without accidental, F with one flat
+        let natural_fifths = match major {
+            Pitch::C => 0,
+            Pitch::D => 2,
+            Pitch::E => 4,
+            Pitch::F => -1,
+            Pitch::G => 1,
+            Pitch::A => 3,
+            Pitch::B => 5,
+        };
+        natural_fifths + 7 * get_accidental_offset(major, major_semitone)
+    }
+
+    pub fn get_key_signature(&self) -> KeySignature {
+        match self.get_fifths() {
+            f if f >= 0 => KeySignature::new(KeySignatureAccidental::Sharp, f as u8),
+            f => KeySignature::new(KeySignatureAccidental::Flat, -f as u8),
+        }
+    }
+
+    //pitch and semitone from C of the degree from 0
+    fn get_degree_semitone(&self, i: i32) -> (Pitch, i32) {
+        let pitch = Pitch::from_diatonic_index(self.tonic.get_diatonic_index() + i);
+        let semitone =
+            self.get_tonic_semitone() + self.mode.get_intervals()[i.rem_euclid(7) as usize];
+        (pitch, semitone)
+    }
+
+    //degree 1 is the tonic, 8 the tonic an octave up, 0 the 7th under it
+    //the accidental is the written one: None follows the key signature, a natural cancels it
+    pub fn get_degree(&self, degree: i32) -> (Pitch, Option<Accidental>) {
+        let (pitch, semitone) = self.get_degree_semitone(degree - 1);
+        let offset = get_accidental_offset(pitch, semitone);
+        (pitch, self.get_written_accidental(pitch, offset))
+    }
+
+    //None follows the key signature, a natural cancels it
+    fn get_written_accidental(&self, pitch: Pitch, offset: i32) -> Option<Accidental> {
+        match Accidental::from_semitone_offset(offset)? {
+            Accidental::Natural if !self.get_key_signature().is_pitch_inside(pitch) => None,
+            a => Some(a),
+        }
+    }
+
+    //the spelling of a semitone in the key: the note of the scale, else the nearest degree raised in a key
+    //without flats or lowered in a key with flats, E# in F# major, Bb in F major, C# in C major
+    //None out of the midi notes
+    pub fn spell(&self, semitone: Semitone, clef: Clef) -> Option<Note> {
+        let sharp_key = self.get_fifths() >= 0;
+        let (pitch, offset, octave) = get_spellings(semitone)
+            .into_iter()
+            .map(|(p, a, o)| (p, a.map(|a| a.get_semitone_offset() as i32).unwrap_or(0), o))
+            .min_by_key(|(p, offset, _)| {
+                let (_, scale_semitone) = self.get_degree_semitone(self.get_degree_of(*p) - 1);
+                let alteration = offset - get_accidental_offset(*p, scale_semitone);
+                let against_key = match sharp_key {
+                    true => alteration < 0,
+                    false => alteration > 0,
+                };
+                (alteration.abs(), against_key)
+            })?;
+        Some(Note::new(
+            pitch,
+            self.get_written_accidental(pitch, offset),
+            octave,
+            clef,
+        ))
+    }
+
+    //the note of the scale `steps` degrees above a note of the scale, None out of the midi notes
+    pub fn get_above(&self, note: &Note, steps: i32) -> Option<Note> {
+        let i = self.get_degree_of(note.pitch) - 1;
+        let (_, low) = self.get_degree_semitone(i);
+        let (_, high) = self.get_degree_semitone(i + steps);
+        let distance = (high - low).rem_euclid(12) + 12 * steps.div_euclid(7);
+        let n = note.get_sounding(self.get_key_signature());
+        let semitone = get_semitone(n.pitch, n.accidental, n.octave)?.0 as i32 + distance;
+        let semitone = u8::try_from(semitone).ok()?;
+        self.spell(Semitone(semitone), note.clef)
+    }
+
+    //the degree of a pitch, from 1 to 7
+    pub fn get_degree_of(&self, pitch: Pitch) -> i32 {
+        (pitch.get_diatonic_index() - self.tonic.get_diatonic_index()).rem_euclid(7) + 1
+    }
+
+    //a 7th a semitone under the tonic
+    pub fn has_leading_tone(&self) -> bool {
+        self.mode.get_intervals()[6] == 11
+    }
+
+    //the 7 notes from the tonic
+    pub fn get_scale(&self) -> Vec<(Pitch, Option<Accidental>)> {
+        (1..=7).map(|d| self.get_degree(d)).collect()
+    }
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} {}",
+            NoteNaming::English.get_name(self.tonic, self.accidental),
+            self.mode
+        )
+    }
+}
+
+//unisons, 4ths, 5ths and octaves are perfect, the others major or minor
+fn is_perfect_number(number: i32) -> bool {
+    matches!(number, 1 | 4 | 5 | 8)
+}
+
+//a 2nd, a 3rd... an octave
+fn get_ordinal(number: u8) -> String {
+    match number {
+        2 => "a 2nd".to_string(),
+        3 => "a 3rd".to_string(),
+        8 => "an octave".to_string(),
+        n => format!("a {}th", n),
+    }
+}
+
+//semitones of the major or perfect interval
+fn get_reference_semitones(number: i32) -> i32 {
+    [0, 2, 4, 5, 7, 9, 11, 12][(number - 1) as usize]
+}
+
+impl Interval {
+    pub fn new(number: u8, quality: Quality) -> Result<Interval, String> {
+        if !(2..=8).contains(&number) {
+            return Err(format!(
+                "interval of {}, only from a 2nd to an octave",
+                number
+            ));
+        }
+        let perfect = is_perfect_number(number as i32);
+        match quality {
+            Quality::Perfect if !perfect => {
+                Err(format!("{} is major or minor", get_ordinal(number)))
+            }
+            Quality::Major | Quality::Minor if perfect => {
+                Err(format!("{} is perfect", get_ordinal(number)))
+            }
+            _ => Ok(Interval { number, quality }),
+        }
+    }
+
+    //every interval but the diminished and augmented ones, with the tritone as an augmented 4th
+    pub fn get_common() -> Vec<Interval> {
+        let mut intervals = Vec::new();
+        for number in 2..=8 {
+            match is_perfect_number(number) {
+                true => intervals.push(Interval::new(number as u8, Quality::Perfect).unwrap()),
+                false => {
+                    intervals.push(Interval::new(number as u8, Quality::Minor).unwrap());
+                    intervals.push(Interval::new(number as u8, Quality::Major).unwrap());
+                }
+            }
+        }
+        intervals.insert(5, Interval::new(4, Quality::Augmented).unwrap());
+        intervals
+    }
+
+    pub fn get_number(&self) -> u8 {
+        self.number
+    }
+
+    pub fn get_semitones(&self) -> i32 {
+        let reference = get_reference_semitones(self.number as i32);
+        match self.quality {
+            Quality::Perfect | Quality::Major => reference,
+            Quality::Minor => reference - 1,
+            Quality::Augmented => reference + 1,
+            Quality::Diminished if is_perfect_number(self.number as i32) => reference - 1,
+            Quality::Diminished => reference - 2,
+        }
+    }
+
+    //from the lower note to the higher one, None out of a 2nd to an octave
+    pub fn between(a: &Note, b: &Note) -> Option<Interval> {
+        let (low, high) = match a.get_step() <= b.get_step() {
+            true => (a, b),
+            false => (b, a),
+        };
+        let number = high.get_step() - low.get_step() + 1;
+        if !(2..=8).contains(&number) {
+            return None;
+        }
+        let semitones = high.get_semitone()?.0 as i32 - low.get_semitone()?.0 as i32;
+        let quality = match (
+            is_perfect_number(number),
+            semitones - get_reference_semitones(number),
+        ) {
+            (true, -1) | (false, -2) => Quality::Diminished,
+            (false, -1) => Quality::Minor,
+            (true, 0) => Quality::Perfect,
+            (false, 0) => Quality::Major,
+            (_, 1) => Quality::Augmented,
+            _ => return None,
+        };
+        Some(Interval {
+            number: number as u8,
+            quality,
+        })
+    }
+
+    //None when it would need more than a double sharp or flat, or out of the midi notes
+    pub fn get_above(&self, n: &Note) -> Option<Note> {
+        self.transpose(n, 1)
+    }
+
+    pub fn get_below(&self, n: &Note) -> Option<Note> {
+        self.transpose(n, -1)
+    }
+
+    //the accidental is always written, a natural too
+    fn transpose(&self, n: &Note, direction: i32) -> Option<Note> {
+        let step = n.get_step() + direction * (self.number as i32 - 1);
+        let semitone = n.get_semitone()?.0 as i32 + direction * self.get_semitones();
+        let (pitch, octave) = from_step(step);
+        //B#-2 is a midi note even if B-2 is not
+        let natural = 24 + octave.0 * 12 + pitch.get_semitone_offset() as i32;
+        let accidental = Accidental::from_semitone_offset(semitone - natural)?;
+        let other = Note::new(pitch, Some(accidental), octave, n.clef);
+        other.get_semitone().map(|_| other)
+    }
+}
+
+//m3, P5, A4
+impl fmt::Display for Interval {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let quality = match self.quality {
+            Quality::Diminished => "d",
+            Quality::Minor => "m",
+            Quality::Perfect => "P",
+            Quality::Major => "M",
+            Quality::Augmented => "A",
+        };
+        write!(f, "{}{}", quality, self.number)
+    }
 }
 
 impl Pitch {
+    //C = 0, D = 1 ... B = 6
+    pub fn get_diatonic_index(&self) -> i32 {
+        match self {
+            Self::C => 0,
+            Self::D => 1,
+            Self::E => 2,
+            Self::F => 3,
+            Self::G => 4,
+            Self::A => 5,
+            Self::B => 6,
+        }
+    }
+
+    pub fn from_diatonic_index(i: i32) -> Pitch {
+        match i.rem_euclid(7) {
+            0 => Self::C,
+            1 => Self::D,
+            2 => Self::E,
+            3 => Self::F,
+            4 => Self::G,
+            5 => Self::A,
+            _ => Self::B,
+        }
+    }
+
     pub fn get_semitone_offset(&self) -> u8 {
         match self {
             Self::A => 9,
@@ -139,19 +783,169 @@ impl Pitch {
     }
 }
 
-impl fmt::Display for Clef {
+//lines and spaces from C0, one step per natural note
+pub fn get_step(pitch: Pitch, octave: Octave) -> i32 {
+    octave.0 * 7 + pitch.get_diatonic_index()
+}
+
+pub fn from_step(step: i32) -> (Pitch, Octave) {
+    (Pitch::from_diatonic_index(step), Octave(step.div_euclid(7)))
+}
+
+impl Clef {
+    //step of the lowest line of the stave
+    pub fn get_bottom_line(&self) -> i32 {
+        match self {
+            //E3
+            Self::Sol => get_step(Pitch::E, Octave(3)),
+            //G1
+            Self::Fa => get_step(Pitch::G, Octave(1)),
+        }
+    }
+
+    //step of the highest line of the stave
+    pub fn get_top_line(&self) -> i32 {
+        self.get_bottom_line() + 8
+    }
+
+    //notes outside the stave and its two next spaces need ledger lines
+    pub fn needs_ledger_lines(&self, step: i32) -> bool {
+        step < self.get_bottom_line() - 1 || step > self.get_top_line() + 1
+    }
+}
+
+impl NoteRange {
+    //both ends have to be midi notes
+    pub fn new(low: (Pitch, Octave), high: (Pitch, Octave)) -> Result<NoteRange, String> {
+        for (pitch, octave) in [low, high] {
+            if get_semitone(pitch, None, octave).is_none() {
+                return Err(format!(
+                    "{} is out of the midi notes",
+                    format_note(pitch, None, octave)
+                ));
+            }
+        }
+        let low = get_step(low.0, low.1);
+        let high = get_step(high.0, high.1);
+        Ok(NoteRange {
+            low: low.min(high),
+            high: low.max(high),
+        })
+    }
+
+    //the stave and up to `below` and `above` ledger lines, with the space after the last one
+    pub fn around_stave(clef: Clef, below: u8, above: u8) -> NoteRange {
+        NoteRange {
+            low: clef.get_bottom_line() - below as i32 * 2 - 1,
+            high: clef.get_top_line() + above as i32 * 2 + 1,
+        }
+    }
+
+    pub fn get_steps(&self) -> std::ops::RangeInclusive<i32> {
+        self.low..=self.high
+    }
+}
+
+impl NoteNaming {
+    pub fn from_name(name: &str) -> Option<NoteNaming> {
+        match name {
+            "english" => Some(Self::English),
+            "solfege" => Some(Self::Solfege),
+            "german" => Some(Self::German),
+            _ => None,
+        }
+    }
+
+    pub fn next(&self) -> NoteNaming {
+        match self {
+            Self::English => Self::Solfege,
+            Self::Solfege => Self::German,
+            Self::German => Self::English,
+        }
+    }
+
+    //a natural sign is not named
+    pub fn get_name(&self, pitch: Pitch, accidental: Option<Accidental>) -> String {
+        match self {
+            Self::English => format!("{:?}{}", pitch, get_accidental_sign(accidental)),
+            Self::Solfege => {
+                let name = match pitch {
+                    Pitch::C => "Do",
+                    Pitch::D => "Ré",
+                    Pitch::E => "Mi",
+                    Pitch::F => "Fa",
+                    Pitch::G => "Sol",
+                    Pitch::A => "La",
+                    Pitch::B => "Si",
+                };
+                format!("{}{}", name, get_accidental_sign(accidental))
+            }
+            Self::German => {
+                let letter = match pitch {
+                    Pitch::B => "H".to_string(),
+                    p => format!("{:?}", p),
+                };
+                match (pitch, accidental) {
+                    (Pitch::B, Some(Accidental::Flat)) => "B".to_string(),
+                    //vowels only take the s: Es, As, Eses
+                    (Pitch::E | Pitch::A, Some(Accidental::Flat)) => letter + "s",
+                    (Pitch::E | Pitch::A, Some(Accidental::DoubleFlat)) => letter + "ses",
+                    (_, Some(Accidental::Flat)) => letter + "es",
+                    (_, Some(Accidental::DoubleFlat)) => letter + "eses",
+                    (_, Some(Accidental::Sharp)) => letter + "is",
+                    (_, Some(Accidental::DoubleSharp)) => letter + "isis",
+                    _ => letter,
+                }
+            }
+        }
+    }
+
+    //for the font of the screen which only has ascii characters
+    pub fn get_ascii_name(&self, pitch: Pitch, accidental: Option<Accidental>) -> String {
+        self.get_name(pitch, accidental).replace('é', "e")
+    }
+
+    //the note of a letter key in the names quiz, in german B is B flat and H is B
+    pub fn get_letter_note(
+        &self,
+        letter: char,
+        accidental: Accidental,
+    ) -> Option<(Pitch, Accidental)> {
+        let pitch = match letter.to_ascii_uppercase() {
+            'A' => Pitch::A,
+            'B' if *self == Self::German => return Some((Pitch::B, Accidental::Flat)),
+            'B' => Pitch::B,
+            'C' => Pitch::C,
+            'D' => Pitch::D,
+            'E' => Pitch::E,
+            'F' => Pitch::F,
+            'G' => Pitch::G,
+            'H' if *self == Self::German => Pitch::B,
+            _ => return None,
+        };
+        Some((pitch, accidental))
+    }
+}
+
+fn get_accidental_sign(accidental: Option<Accidental>) -> &'static str {
+    match accidental {
+        Some(Accidental::Sharp) => "#",
+        Some(Accidental::Flat) => "b",
+        Some(Accidental::DoubleSharp) => "x",
+        Some(Accidental::DoubleFlat) => "bb",
+        _ => "",
+    }
+}
+
+impl fmt::Display for NoteNaming {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "{:?}", self)
     }
 }
 
-impl Distribution<Accidental> for Standard {
-    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Accidental {
-        match rng.gen_range(0..=2) {
-            0 => Accidental::Flat,
-            1 => Accidental::Natural,
-            _ => Accidental::Sharp,
-        }
+impl fmt::Display for Clef {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self)
     }
 }
 
@@ -173,26 +967,257 @@ impl Distribution<KeySignatureAccidental> for Standard {
     }
 }
 
-impl Distribution<KeySignature> for Standard {
+//`what` is the kind of value weighted
+fn check_weights(weights: &[u32], what: &str) -> Result<(), String> {
+    if weights.iter().any(|w| *w > MAX_WEIGHT) {
+        return Err(format!("weights are at most {}", MAX_WEIGHT));
+    }
+    match weights.iter().any(|w| *w > 0) {
+        true => Ok(()),
+        false => Err(format!("at least one {} needs a weight", what)),
+    }
+}
+
+impl PitchWeights {
+    pub fn new(weights: [u32; 7]) -> Result<PitchWeights, String> {
+        check_weights(&weights, "pitch")?;
+        Ok(PitchWeights(weights))
+    }
+
+    pub fn get_weight(&self, p: Pitch) -> u32 {
+        self.0[p.get_diatonic_index() as usize]
+    }
+}
+
+//every pitch is as likely
+impl Default for PitchWeights {
+    fn default() -> Self {
+        PitchWeights([1; 7])
+    }
+}
+
+impl AccidentalWeights {
+    pub fn new(
+        none: u32,
+        sharp: u32,
+        flat: u32,
+        natural: u32,
+    ) -> Result<AccidentalWeights, String> {
+        check_weights(&[none, sharp, flat, natural], "accidental")?;
+        Ok(AccidentalWeights {
+            none,
+            sharp,
+            flat,
+            natural,
+        })
+    }
+
+    //double accidentals only come from the keys and the intervals
+    pub fn get_weight(&self, a: Option<Accidental>) -> u32 {
+        match a {
+            None => self.none,
+            Some(Accidental::Sharp) => self.sharp,
+            Some(Accidental::Flat) => self.flat,
+            Some(Accidental::Natural) => self.natural,
+            Some(Accidental::DoubleSharp | Accidental::DoubleFlat) => 0,
+        }
+    }
+}
+
+//half of the notes without accidental, the others sharp, flat or natural
+impl Default for AccidentalWeights {
+    fn default() -> Self {
+        AccidentalWeights {
+            none: 3,
+            sharp: 1,
+            flat: 1,
+            natural: 1,
+        }
+    }
+}
+
+impl Distribution<Option<Accidental>> for AccidentalWeights {
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Option<Accidental> {
+        let weights = [self.none, self.sharp, self.flat, self.natural];
+        match WeightedIndex::new(weights).unwrap().sample(rng) {
+            0 => None,
+            1 => Some(Accidental::Sharp),
+            2 => Some(Accidental::Flat),
+            _ => Some(Accidental::Natural),
+        }
+    }
+}
+
+impl KeySignatureWeights {
+    pub fn new(weights: [u32; 8]) -> Result<KeySignatureWeights, String> {
+        check_weights(&weights, "key signature")?;
+        Ok(KeySignatureWeights(weights))
+    }
+}
+
+//mostly without key signature, sometimes from 1 to 5 sharps or flats
+impl Default for KeySignatureWeights {
+    fn default() -> Self {
+        KeySignatureWeights([20, 1, 1, 1, 1, 1, 0, 0])
+    }
+}
+
+impl Distribution<KeySignature> for KeySignatureWeights {
     fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> KeySignature {
-        let nb = match rng.gen_bool(0.2) {
-            true => rng.gen_range(1..6),
-            false => 0
-        };
-        KeySignature::new(rng.sample(Standard), nb)
+        let nb = WeightedIndex::new(self.0).unwrap().sample(rng);
+        KeySignature::new(rng.sample(Standard), nb as u8)
     }
 }
 
-impl Distribution<Pitch> for Standard {
-    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Pitch {
-        match rng.gen_range(0..=7) {
-            0 => Pitch::A,
-            1 => Pitch::B,
-            2 => Pitch::C,
-            3 => Pitch::D,
-            4 => Pitch::E,
-            5 => Pitch::F,
-            _ => Pitch::G,
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn note_range_in_midi_notes() {
+        let range = NoteRange::new((Pitch::A, Octave(4)), (Pitch::C, Octave(3))).unwrap();
+        assert_eq!(
+            range.get_steps(),
+            get_step(Pitch::C, Octave(3))..=get_step(Pitch::A, Octave(4))
+        );
+        assert!(NoteRange::new((Pitch::C, Octave(-2)), (Pitch::G, Octave(8))).is_ok());
+        assert!(NoteRange::new((Pitch::A, Octave(8)), (Pitch::B, Octave(8))).is_err());
+        assert!(NoteRange::new((Pitch::B, Octave(-3)), (Pitch::C, Octave(3))).is_err());
+    }
+
+    #[test]
+    fn weights_are_capped() {
+        assert!(AccidentalWeights::new(u32::MAX, 1, 0, 0).is_err());
+        assert!(AccidentalWeights::new(0, 0, 0, 0).is_err());
+        assert!(AccidentalWeights::new(MAX_WEIGHT, 0, 0, 0).is_ok());
+        assert!(PitchWeights::new([u32::MAX, 1, 1, 1, 1, 1, 1]).is_err());
+        assert!(KeySignatureWeights::new([0; 8]).is_err());
+        assert!(KeySignatureWeights::new([MAX_WEIGHT + 1, 0, 0, 0, 0, 0, 0, 0]).is_err());
+    }
+
+    #[test]
+    fn spell_in_key() {
+        let f_sharp = Key::new(Pitch::F, Some(Accidental::Sharp), Mode::Major).unwrap();
+        let n = f_sharp.spell(Semitone(65), Clef::Sol).unwrap();
+        assert_eq!(
+            (n.pitch, n.accidental, n.octave),
+            (Pitch::E, Some(Accidental::Sharp), Octave(3))
+        );
+        let n = f_sharp.spell(Semitone(60), Clef::Sol).unwrap();
+        assert_eq!(
+            (n.pitch, n.accidental, n.octave),
+            (Pitch::B, Some(Accidental::Sharp), Octave(2))
+        );
+
+        let f = Key::new(Pitch::F, None, Mode::Major).unwrap();
+        let n = f.spell(Semitone(70), Clef::Sol).unwrap();
+        assert_eq!((n.pitch, n.accidental), (Pitch::B, Some(Accidental::Flat)));
+
+        let d_minor = Key::new(Pitch::D, None, Mode::HarmonicMinor).unwrap();
+        let n = d_minor.spell(Semitone(71), Clef::Sol).unwrap();
+        assert_eq!(
+            (n.pitch, n.accidental),
+            (Pitch::B, Some(Accidental::Natural))
+        );
+        let n = d_minor.spell(Semitone(61), Clef::Sol).unwrap();
+        assert_eq!((n.pitch, n.accidental), (Pitch::C, Some(Accidental::Sharp)));
+        assert!(d_minor.spell(Semitone(128), Clef::Sol).is_none());
+    }
+
+    #[test]
+    fn interval_errors() {
+        assert_eq!(
+            Interval::new(3, Quality::Perfect),
+            Err("a 3rd is major or minor".to_string())
+        );
+        assert_eq!(
+            Interval::new(8, Quality::Major),
+            Err("an octave is perfect".to_string())
+        );
+        assert!(Interval::new(9, Quality::Perfect).is_err());
+    }
+
+    #[test]
+    fn scale_steps_above() {
+        //the dominant chord of D harmonic minor has the raised leading tone
+        let d_minor = Key::new(Pitch::D, None, Mode::HarmonicMinor).unwrap();
+        let a = Note::new(Pitch::A, None, Octave(3), Clef::Sol);
+        let third = d_minor.get_above(&a, 2).unwrap();
+        assert_eq!(
+            (third.pitch, third.accidental, third.octave),
+            (Pitch::C, Some(Accidental::Sharp), Octave(4))
+        );
+        let fifth = d_minor.get_above(&a, 4).unwrap();
+        assert_eq!(
+            (fifth.pitch, fifth.accidental, fifth.octave),
+            (Pitch::E, None, Octave(4))
+        );
+        let octave = d_minor.get_above(&a, 7).unwrap();
+        assert_eq!((octave.pitch, octave.octave), (Pitch::A, Octave(4)));
+
+        let g = Note::new(Pitch::G, None, Octave(8), Clef::Sol);
+        assert!(Key::new(Pitch::C, None, Mode::Major)
+            .unwrap()
+            .get_above(&g, 2)
+            .is_none());
+    }
+
+    #[test]
+    fn notes_round_trip() {
+        for s in [
+            "C#4", "Bb2", "Fx3", "Ebb5", "Cn4", "C-1", "G9", "B#3", "Cb4",
+        ] {
+            let (pitch, accidental, octave) = parse_note(s).unwrap();
+            assert_eq!(format_note(pitch, accidental, octave), s);
+        }
+        assert_eq!(parse_note("F##3"), parse_note("Fx3"));
+        assert_eq!(parse_note("c#4"), parse_note("C#4"));
+    }
+
+    #[test]
+    fn notes_parsed_as_midi_notes() {
+        let semitone = |s: &str| parse_note(s).and_then(|(p, a, o)| get_semitone(p, a, o));
+        assert_eq!(semitone("C4"), Some(Semitone(60)));
+        assert_eq!(semitone("C#4"), Some(Semitone(61)));
+        assert_eq!(semitone("Bb2"), Some(Semitone(46)));
+        assert_eq!(semitone("Fx3"), Some(Semitone(55)));
+        assert_eq!(semitone("Ebb5"), Some(Semitone(74)));
+        assert_eq!(semitone("C-1"), Some(Semitone(0)));
+        assert_eq!(semitone("G9"), Some(Semitone(127)));
+        assert_eq!(semitone("A9"), None);
+        assert_eq!(semitone("Cb-1"), None);
+        assert_eq!(semitone("G#9"), None);
+        //the octave is the one of the letter
+        assert_eq!(semitone("B#3"), Some(Semitone(60)));
+        assert_eq!(semitone("Cb4"), Some(Semitone(59)));
+        assert_eq!(get_semitone(Pitch::C, None, Octave(i32::MAX)), None);
+    }
+
+    #[test]
+    fn bad_notes() {
+        for s in ["", "C", "4", "H4", "C#", "C#x4", "Cbbb4", "C4.5", "#4"] {
+            assert_eq!(parse_note(s), None, "{}", s);
+        }
+    }
+
+    #[test]
+    fn spellings_of_midi_notes() {
+        let spellings = |semitone: u8| -> Vec<String> {
+            get_spellings(Semitone(semitone))
+                .iter()
+                .map(|(p, a, o)| format_note(*p, *a, *o))
+                .collect()
+        };
+        assert_eq!(spellings(60), ["C4", "Dbb4", "B#3"]);
+        assert_eq!(spellings(59), ["Cb4", "Ax3", "B3"]);
+        assert_eq!(spellings(61), ["C#4", "Db4", "Bx3"]);
+        assert_eq!(spellings(0), ["C-1", "Dbb-1", "B#-2"]);
+        assert!(get_spellings(Semitone(200)).is_empty());
+        //every spelling goes back to its semitone
+        for semitone in 0..=127 {
+            for (p, a, o) in get_spellings(Semitone(semitone)) {
+                assert_eq!(get_semitone(p, a, o), Some(Semitone(semitone)));
+            }
         }
     }
 }
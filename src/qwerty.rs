@@ -0,0 +1,80 @@
+use std::collections::HashMap;
+
+use sdl2::keyboard::Scancode;
+
+use train_piano::music::{NoteNaming, Pitch, Semitone};
+
+//a piano on the computer keyboard, keys are taken by their place so any layout works
+//  W E   T Y U   O P
+// A S D F G H J K L ; '
+//the row of A is C D E F G A B C D E F, the row above holds the black keys
+//Z and X go an octave down and up
+pub struct Qwerty {
+    //semitone of the key A
+    c: u8,
+    //the semitone of a key is kept until it is released, even if the octave changed
+    held: HashMap<Scancode, Semitone>,
+}
+
+//semitones from the C of the key A
+fn get_offset(sc: Scancode) -> Option<u8> {
+    let offset = match sc {
+        Scancode::A => 0,
+        Scancode::W => 1,
+        Scancode::S => 2,
+        Scancode::E => 3,
+        Scancode::D => 4,
+        Scancode::F => 5,
+        Scancode::T => 6,
+        Scancode::G => 7,
+        Scancode::Y => 8,
+        Scancode::H => 9,
+        Scancode::U => 10,
+        Scancode::J => 11,
+        Scancode::K => 12,
+        Scancode::O => 13,
+        Scancode::L => 14,
+        Scancode::P => 15,
+        Scancode::Semicolon => 16,
+        Scancode::Apostrophe => 17,
+        _ => return None,
+    };
+    Some(offset)
+}
+
+impl Qwerty {
+    pub fn new() -> Qwerty {
+        Qwerty {
+            //middle C
+            c: 60,
+            held: HashMap::new(),
+        }
+    }
+
+    //keys of the piano and of the octave shift
+    pub fn is_mapped(sc: Scancode) -> bool {
+        get_offset(sc).is_some() || sc == Scancode::Z || sc == Scancode::X
+    }
+
+    //the semitone pressed, None for the octave shift
+    pub fn press(&mut self, sc: Scancode) -> Option<Semitone> {
+        match sc {
+            Scancode::Z => self.c = self.c.saturating_sub(12).max(12),
+            Scancode::X => self.c = (self.c + 12).min(96),
+            _ => {}
+        }
+        let s = Semitone(self.c + get_offset(sc)?);
+        self.held.insert(sc, s);
+        Some(s)
+    }
+
+    pub fn release(&mut self, sc: Scancode) -> Option<Semitone> {
+        self.held.remove(&sc)
+    }
+
+    //name of the C of the key A, middle C is C4
+    pub fn get_octave_name(&self, naming: NoteNaming) -> String {
+        let c = naming.get_ascii_name(Pitch::C, None);
+        format!("{}{}", c, self.c as i32 / 12 - 1)
+    }
+}
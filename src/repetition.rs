@@ -0,0 +1,77 @@
+use std::collections::HashMap;
+
+use crate::music::*;
+use crate::stave::Note;
+
+//answers between two reviews of a note, by box
+//a missed note goes back to the first box, a note answered well and fast goes to the next one
+const INTERVALS: [u32; 5] = [2, 5, 12, 30, 80];
+//a right answer slower than this is not learned yet, the note stays in its box
+pub const SLOW_ANSWER_MS: u32 = 2000;
+
+//a written note, whatever its color or length
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub struct NoteKey {
+    pub pitch: Pitch,
+    pub accidental: Option<Accidental>,
+    pub octave: Octave,
+    pub clef: Clef,
+}
+
+impl NoteKey {
+    pub fn new(note: &Note) -> NoteKey {
+        NoteKey {
+            pitch: note.pitch,
+            accidental: note.accidental,
+            octave: note.octave,
+            clef: note.clef,
+        }
+    }
+
+    //None out of the midi notes
+    pub fn get_semitone(&self) -> Option<Semitone> {
+        Note::new(self.pitch, self.accidental, self.octave, self.clef).get_semitone()
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Card {
+    pub i_box: usize,
+    //the note is due again when the clock is there
+    pub due: u32,
+}
+
+//Leitner boxes, the time is counted in answered notes so a pause does not make every note due
+#[derive(Debug, Default)]
+pub struct Repetition {
+    clock: u32,
+    cards: HashMap<NoteKey, Card>,
+}
+
+impl Repetition {
+    pub fn new() -> Repetition {
+        Repetition::default()
+    }
+
+    //reaction_ms is None when the time to answer does not count (timed mode)
+    pub fn answer(&mut self, key: NoteKey, right: bool, reaction_ms: Option<u32>) {
+        self.clock += 1;
+        let card = self.cards.entry(key).or_default();
+        if !right {
+            card.i_box = 0;
+        } else if reaction_ms.unwrap_or(0) <= SLOW_ANSWER_MS {
+            card.i_box = (card.i_box + 1).min(INTERVALS.len() - 1);
+        }
+        card.due = self.clock + INTERVALS[card.i_box];
+    }
+
+    //multiplies the weight of the note in the random staves
+    //due notes of the first boxes come the most, notes not due the least
+    pub fn get_factor(&self, key: &NoteKey) -> u32 {
+        match self.cards.get(key) {
+            None => 4,
+            Some(c) if c.due <= self.clock => 4 + 4 * (INTERVALS.len() - 1 - c.i_box) as u32,
+            Some(_) => 1,
+        }
+    }
+}
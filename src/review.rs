@@ -0,0 +1,147 @@
+use std::collections::HashMap;
+
+#[cfg(feature = "sdl")]
+use sdl2::gfx::primitives::DrawRenderer;
+#[cfg(feature = "sdl")]
+use sdl2::render::WindowCanvas;
+
+use crate::generator::StaveGenerator;
+use crate::graphics::{Color, Point};
+use crate::history::Session;
+use crate::keyboard::Keyboard;
+use crate::music::*;
+use crate::stave::*;
+
+//answers and misses
+#[derive(Debug, Clone, Copy, Default)]
+struct Count {
+    answers: u32,
+    misses: u32,
+}
+
+//green without error, red when always missed, gray when never played
+fn get_heat_color(count: Option<&Count>) -> Color {
+    match count {
+        Some(c) if c.answers > 0 => {
+            let rate = c.misses as f32 / c.answers as f32;
+            Color::RGB((230. * rate) as u8, (180. * (1. - rate)) as u8, 0)
+        }
+        _ => Color::GRAY,
+    }
+}
+
+//error rate of every line and space played, on the staves and on the keys
+pub struct Review {
+    staves: Vec<Stave>,
+    keyboard: Keyboard,
+    semitone_counts: HashMap<u8, Count>,
+}
+
+impl Review {
+    pub fn new(
+        sessions: &[&Session],
+        generator: &StaveGenerator,
+        x_pos: i32,
+        size: Point,
+    ) -> Review {
+        //accidentals are counted with their line or space
+        let mut step_counts: HashMap<(Clef, i32), Count> = HashMap::new();
+        let mut semitone_counts: HashMap<u8, Count> = HashMap::new();
+        for r in sessions.iter().flat_map(|s| s.results.iter()) {
+            let step = get_step(r.key.pitch, r.key.octave);
+            //a note out of the midi notes has no key
+            let mut counts = vec![step_counts.entry((r.key.clef, step)).or_default()];
+            if let Some(s) = r.key.get_semitone() {
+                counts.push(semitone_counts.entry(s.0).or_default());
+            }
+            for c in counts {
+                c.answers += 1;
+                if !r.right {
+                    c.misses += 1;
+                }
+            }
+        }
+
+        let mut staves = Vec::new();
+        let mut semitones = Vec::new();
+        for clef in [Clef::Sol, Clef::Fa] {
+            //the range of the settings and every note played out of it
+            let range = generator.get_range(clef).get_steps();
+            let played = step_counts.keys().filter(|k| k.0 == clef).map(|k| k.1);
+            let low = played.clone().chain([*range.start()]).min().unwrap();
+            let high = played.chain([*range.end()]).max().unwrap();
+
+            let notes: Vec<Note> = (low..=high)
+                .map(|step| {
+                    let (pitch, octave) = from_step(step);
+                    let mut n = Note::new(pitch, None, octave, clef);
+                    n.color = get_heat_color(step_counts.get(&(clef, step)));
+                    n
+                })
+                .collect();
+            semitones.extend(notes.iter().filter_map(|n| n.get_semitone()).map(|s| s.0));
+            staves.push(Review::new_stave(notes, clef, x_pos, size));
+        }
+
+        //a sharp above the highest note and a flat under the lowest one, inside the midi notes
+        let low = Semitone(semitones.iter().min().unwrap().saturating_sub(1));
+        let high = Semitone((semitones.iter().max().unwrap() + 1).min(127));
+        let keyboard = Keyboard::new(Point::new(x_pos, 440), Point::new(size.x, 100), low, high);
+
+        Review {
+            staves,
+            keyboard,
+            semitone_counts,
+        }
+    }
+
+    //the notes one after the other in three measures, the last one ends with rests
+    fn new_stave(notes: Vec<Note>, clef: Clef, x_pos: i32, size: Point) -> Stave {
+        let nb_measures = 3;
+        let per_measure = notes.len().div_ceil(nb_measures);
+        let time_signature = TimeSignature(per_measure as u8, NoteValue::Quarter);
+        let key_signature = KeySignature::new(KeySignatureAccidental::Sharp, 0);
+        let quarter = NoteLength::new(NoteValue::Quarter, false);
+
+        let mut s = Stave::new(x_pos, size, vec![clef], key_signature, time_signature);
+        s.draw_time_signature = false;
+        for chunk in notes.chunks(per_measure) {
+            let mut events: Vec<MeasureEvent> = chunk
+                .iter()
+                .map(|n| MeasureEvent::new(vec![*n], quarter))
+                .collect();
+            while events.len() < per_measure {
+                events.push(MeasureEvent::new(Vec::new(), quarter));
+            }
+            s.add_measure(Measure::new(events, key_signature, time_signature).unwrap());
+        }
+        s
+    }
+
+    #[cfg(feature = "sdl")]
+    pub fn draw(&self, canvas: &WindowCanvas) {
+        canvas
+            .string(20, 20, "Review (F7: back)", Color::BLACK)
+            .unwrap();
+        canvas
+            .string(
+                20,
+                35,
+                "green: no error, red: always missed, gray: not played",
+                Color::BLACK,
+            )
+            .unwrap();
+
+        let mut y = 110;
+        for s in self.staves.iter() {
+            s.draw(y, canvas);
+            y += 180;
+        }
+
+        self.keyboard.draw(canvas, |s| {
+            self.semitone_counts
+                .get(&s.0)
+                .map(|c| get_heat_color(Some(c)))
+        });
+    }
+}
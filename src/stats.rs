@@ -0,0 +1,193 @@
+#[cfg(feature = "sdl")]
+use sdl2::gfx::primitives::DrawRenderer;
+#[cfg(feature = "sdl")]
+use sdl2::render::WindowCanvas;
+
+#[cfg(feature = "sdl")]
+use crate::graphics::Color;
+use crate::history::*;
+
+pub const SECONDS_DAY: u64 = 60 * 60 * 24;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Period {
+    Day,
+    //from monday
+    Week,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct PeriodStats {
+    //days since 1970 of the first day of the period
+    pub first_day: u64,
+    pub answers: u32,
+    pub rights: u32,
+    pub mean_reaction_ms: Option<u32>,
+}
+
+impl Period {
+    fn get_nb_days(&self) -> u64 {
+        match self {
+            Self::Day => 1,
+            Self::Week => 7,
+        }
+    }
+
+    //first day of the period holding the day, 1970-01-01 was a thursday
+    fn get_first_day(&self, day: u64) -> u64 {
+        match self {
+            Self::Day => day,
+            Self::Week => day.saturating_sub((day + 3) % 7),
+        }
+    }
+}
+
+impl PeriodStats {
+    //from 0 to 1
+    pub fn get_accuracy(&self) -> Option<f32> {
+        match self.answers {
+            0 => None,
+            a => Some(self.rights as f32 / a as f32),
+        }
+    }
+}
+
+//the last nb periods until now, the oldest first, empty periods included
+//days are UTC days
+pub fn get_period_stats(
+    sessions: &[&Session],
+    period: Period,
+    nb: u64,
+    now: u64,
+) -> Vec<PeriodStats> {
+    let last = period.get_first_day(now / SECONDS_DAY);
+    let first = last.saturating_sub((nb - 1) * period.get_nb_days());
+
+    let mut stats = Vec::new();
+    let mut first_day = first;
+    while first_day <= last {
+        let mut s = PeriodStats {
+            first_day,
+            answers: 0,
+            rights: 0,
+            mean_reaction_ms: None,
+        };
+        let mut reactions = Vec::new();
+        for session in sessions.iter() {
+            if period.get_first_day(session.start / SECONDS_DAY) != first_day {
+                continue;
+            }
+            for r in session.results.iter() {
+                s.answers += 1;
+                if r.right {
+                    s.rights += 1;
+                    reactions.extend(r.reaction_ms);
+                }
+            }
+        }
+        if !reactions.is_empty() {
+            s.mean_reaction_ms = Some(reactions.iter().sum::<u32>() / reactions.len() as u32);
+        }
+        stats.push(s);
+        first_day += period.get_nb_days();
+    }
+    stats
+}
+
+//mean and 90th percentile of the reaction times of the right answers
+//9 answers out of 10 are faster than the percentile
+pub fn get_reaction_stats(results: &[NoteResult]) -> Option<(u32, u32)> {
+    let mut reactions: Vec<u32> = results
+        .iter()
+        .filter(|r| r.right)
+        .filter_map(|r| r.reaction_ms)
+        .collect();
+    if reactions.is_empty() {
+        return None;
+    }
+    reactions.sort();
+    let mean = reactions.iter().sum::<u32>() / reactions.len() as u32;
+    let p90 = reactions[(reactions.len() * 9 / 10).min(reactions.len() - 1)];
+    Some((mean, p90))
+}
+
+//YYYY-MM-DD of a number of days since 1970
+pub fn format_day(day: u64) -> String {
+    //from http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = day as i64 + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = doy - (153 * mp + 2) / 5 + 1;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 };
+    let y = yoe + era * 400 + if m <= 2 { 1 } else { 0 };
+    format!("{:04}-{:02}-{:02}", y, m, d)
+}
+
+//accuracy and speed of the last days and weeks
+#[cfg(feature = "sdl")]
+pub fn draw_stats(sessions: &[&Session], canvas: &WindowCanvas) {
+    canvas
+        .string(20, 20, "Statistics (F6: back)", Color::BLACK)
+        .unwrap();
+
+    let now = get_now();
+    draw_period_stats(
+        &get_period_stats(sessions, Period::Day, 14, now),
+        "Day",
+        20,
+        canvas,
+    );
+    draw_period_stats(
+        &get_period_stats(sessions, Period::Week, 8, now),
+        "Week of",
+        420,
+        canvas,
+    );
+}
+
+#[cfg(feature = "sdl")]
+fn draw_period_stats(stats: &[PeriodStats], title: &str, x: i16, canvas: &WindowCanvas) {
+    canvas.string(x, 50, title, Color::BLACK).unwrap();
+    canvas.string(x + 90, 50, "notes", Color::BLACK).unwrap();
+    canvas
+        .string(x + 140, 50, "accuracy", Color::BLACK)
+        .unwrap();
+    canvas.string(x + 285, 50, "time", Color::BLACK).unwrap();
+
+    let mut y = 70;
+    for s in stats.iter() {
+        canvas
+            .string(x, y, &format_day(s.first_day), Color::BLACK)
+            .unwrap();
+        canvas
+            .string(x + 90, y, &s.answers.to_string(), Color::BLACK)
+            .unwrap();
+        if let Some(a) = s.get_accuracy() {
+            //bar of 100px for 100%
+            canvas
+                .rectangle(x + 140, y - 1, x + 240, y + 9, Color::GRAY)
+                .unwrap();
+            canvas
+                .box_(
+                    x + 140,
+                    y - 1,
+                    x + 140 + (a * 100.) as i16,
+                    y + 9,
+                    Color::GREEN,
+                )
+                .unwrap();
+            canvas
+                .string(x + 245, y, &format!("{}%", (a * 100.) as u32), Color::BLACK)
+                .unwrap();
+        }
+        if let Some(ms) = s.mean_reaction_ms {
+            canvas
+                .string(x + 285, y, &format!("{}ms", ms), Color::BLACK)
+                .unwrap();
+        }
+        y += 25;
+    }
+}
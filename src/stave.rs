@@ -0,0 +1,778 @@
+use std::collections::HashMap;
+
+#[cfg(feature = "sdl")]
+use sdl2::gfx::primitives::DrawRenderer;
+#[cfg(feature = "sdl")]
+use sdl2::render::WindowCanvas;
+
+use crate::graphics::{Color, Point};
+use crate::music::*;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Note {
+    pub pitch: Pitch,
+    pub accidental: Option<Accidental>,
+    pub octave: Octave,
+    //the note is written on the stave of this clef
+    pub clef: Clef,
+    pub color: Color,
+    pub draw_acci: bool,
+}
+
+impl Note {
+    pub fn new(pitch: Pitch, accidental: Option<Accidental>, octave: Octave, clef: Clef) -> Note {
+        Note {
+            pitch,
+            accidental,
+            octave,
+            clef,
+            color: Color::BLACK,
+            draw_acci: true,
+        }
+    }
+    //the note `steps` lines/spaces above, following the key signature
+    pub fn get_above(&self, steps: i32) -> Note {
+        let (pitch, octave) = from_step(self.get_step() + steps);
+        Note::new(pitch, None, octave, self.clef)
+    }
+    pub fn get_step(&self) -> i32 {
+        get_step(self.pitch, self.octave)
+    }
+    //the accidental of the key signature written out, as Measure::new does
+    pub fn get_sounding(&self, key_signature: KeySignature) -> Note {
+        let mut n = *self;
+        if n.accidental.is_none() && key_signature.is_pitch_inside(n.pitch) {
+            n.accidental = Some(key_signature.get_accidental());
+        }
+        n
+    }
+    //None out of the midi notes
+    pub fn get_semitone(&self) -> Option<Semitone> {
+        get_semitone(self.pitch, self.accidental, self.octave)
+    }
+}
+
+//Note is same independently of its color
+impl PartialEq for Note {
+    fn eq(&self, other: &Self) -> bool {
+        match (self.get_semitone(), other.get_semitone()) {
+            (Some(a), Some(b)) => a == b,
+            //out of the midi notes, the same written note
+            _ => (self.get_step(), self.accidental) == (other.get_step(), other.accidental),
+        }
+    }
+}
+impl Eq for Note {}
+
+//what is played at once: one note, a chord, or a rest without notes
+pub struct MeasureEvent {
+    pub notes: Vec<Note>,
+    pub length: NoteLength,
+    //tied to the previous event, its notes are held and not played again
+    pub tied: bool,
+    pub pedal: Option<PedalMark>,
+}
+
+impl MeasureEvent {
+    pub fn new(notes: Vec<Note>, length: NoteLength) -> MeasureEvent {
+        MeasureEvent {
+            notes,
+            length,
+            tied: false,
+            pedal: None,
+        }
+    }
+
+    //nothing to play for a rest or a tied event
+    pub fn is_played(&self) -> bool {
+        !self.notes.is_empty() && !self.tied
+    }
+
+    pub fn set_color(&mut self, color: Color) {
+        for n in self.notes.iter_mut() {
+            n.color = color;
+        }
+    }
+
+    //semitones to play, without duplicates
+    pub fn get_semitones(&self) -> Vec<Semitone> {
+        let mut semitones: Vec<Semitone> = Vec::new();
+        for s in self.notes.iter().filter_map(|n| n.get_semitone()) {
+            if !semitones.contains(&s) {
+                semitones.push(s);
+            }
+        }
+        semitones
+    }
+}
+
+pub struct Measure {
+    pub events: Vec<MeasureEvent>,
+}
+
+impl Measure {
+    pub fn new(
+        mut events: Vec<MeasureEvent>,
+        key_sign: KeySignature,
+        time_sign: TimeSignature,
+    ) -> Result<Measure, String> {
+        //the events must fill the measure
+        let ticks: u32 = events.iter().map(|e| e.length.get_ticks()).sum();
+        if ticks != time_sign.get_measure_ticks() {
+            return Err(format!(
+                "measure of {} ticks instead of {} for {}",
+                ticks,
+                time_sign.get_measure_ticks(),
+                time_sign
+            ));
+        }
+
+        //a tied note keeps the accidental of the note before without drawing it
+        for n in events
+            .iter_mut()
+            .filter(|e| e.tied)
+            .flat_map(|e| e.notes.iter_mut())
+        {
+            n.draw_acci = false;
+        }
+
+        //we need to treat notes to have coerent accidentals
+        //an accidental only lasts on its own stave
+        let mut previous_accidentals: HashMap<(Clef, Pitch), Accidental> = HashMap::new();
+
+        for n in events
+            .iter_mut()
+            .filter(|e| !e.tied)
+            .flat_map(|e| e.notes.iter_mut())
+        {
+            if let Some(acci) = previous_accidentals.get(&(n.clef, n.pitch)) {
+                if n.accidental.is_some() {
+                    if n.accidental.unwrap() == *acci {
+                        n.draw_acci = false;
+                    } else {
+                        previous_accidentals.insert((n.clef, n.pitch), n.accidental.unwrap());
+                    }
+                }
+                //we convert to what was previous
+                else {
+                    n.accidental = Some(*acci);
+                    //but we dont draw accidental
+                    n.draw_acci = false;
+                }
+            } else {
+                //first time we encounter this accidental
+                if n.accidental.is_some() {
+                    if key_sign.is_pitch_inside(n.pitch) {
+                        if key_sign.accidental_match(n.accidental.unwrap()) {
+                            n.draw_acci = false;
+                        } else {
+                            previous_accidentals.insert((n.clef, n.pitch), n.accidental.unwrap());
+                        }
+                    } else {
+                        previous_accidentals.insert((n.clef, n.pitch), n.accidental.unwrap());
+                        if n.accidental == Some(Accidental::Natural) {
+                            n.draw_acci = false;
+                        }
+                    }
+                } else if key_sign.is_pitch_inside(n.pitch) {
+                    *n = n.get_sounding(key_sign);
+                    n.draw_acci = false;
+                }
+            }
+        }
+
+        Ok(Measure { events })
+    }
+
+    //ticks from the start of the measure to the event
+    fn get_event_offset(&self, i_event: usize) -> u32 {
+        self.events[..i_event]
+            .iter()
+            .map(|e| e.length.get_ticks())
+            .sum()
+    }
+}
+
+#[cfg(feature = "sdl")]
+fn get_factor_gap_octave(o: &Octave, clef: &Clef) -> i32 {
+    match clef {
+        Clef::Sol => (4 - o.0) * 7,
+        Clef::Fa => (2 - o.0) * 7,
+    }
+}
+
+#[cfg(feature = "sdl")]
+fn get_factor_gap_pitch(p: &Pitch, clef: &Clef) -> i32 {
+    let mut r = match p {
+        Pitch::A => -5,
+        Pitch::B => -6,
+        Pitch::C => 0,
+        Pitch::D => -1,
+        Pitch::E => -2,
+        Pitch::F => -3,
+        Pitch::G => -4,
+    };
+    if *clef == Clef::Fa {
+        r += 2;
+    }
+    r + 3
+}
+
+pub struct Stave {
+    pub x_pos: i32,
+    pub size: Point,
+    //height between two consecutives notes
+    //= radius of notes
+    //gap*2 = gap between two lines
+    pub gap: i32,
+    //one clef, or Sol and Fa joined for a grand staff
+    pub clefs: Vec<Clef>,
+    pub key_signature: KeySignature,
+    pub time_signature: TimeSignature,
+    //hidden when the measures are not music, as in the review screen
+    pub draw_time_signature: bool,
+    pub measures: Vec<Measure>,
+}
+
+impl Stave {
+    #[allow(dead_code)]
+    pub fn new(
+        x_pos: i32,
+        size: Point,
+        clefs: Vec<Clef>,
+        key_signature: KeySignature,
+        time_signature: TimeSignature,
+    ) -> Stave {
+        let gap = size.y / 10;
+
+        Stave {
+            x_pos,
+            size,
+            gap,
+            measures: Vec::new(),
+            key_signature,
+            time_signature,
+            draw_time_signature: true,
+            clefs,
+        }
+    }
+
+    pub fn add_measure(&mut self, m: Measure) {
+        self.measures.push(m);
+    }
+
+    //y of the top line of the stave of this clef, from the top of the first one
+    #[cfg(feature = "sdl")]
+    fn get_clef_y(&self, clef: &Clef) -> i32 {
+        let i = self.clefs.iter().position(|c| c == clef).unwrap_or(0);
+        i as i32 * self.gap * 16
+    }
+
+    //from the top line of the first stave to the bottom line of the last one
+    pub fn get_height(&self) -> i32 {
+        (self.clefs.len() as i32 - 1) * self.gap * 16 + self.gap * 8
+    }
+
+    //x of the bar line starting the measure
+    fn get_measure_x(&self, i_measure: usize) -> i32 {
+        let small_gap_x = self.size.x / 60;
+        let gap_x = self.size.x / 18;
+        //after the key signature and the time signature
+        self.x_pos + small_gap_x * 8 + 16 + i_measure as i32 * gap_x * 5
+    }
+
+    //ticks from the start of the stave to the event
+    pub fn get_ticks_before(&self, i_measure: usize, i_event: usize) -> u32 {
+        i_measure as u32 * self.time_signature.get_measure_ticks()
+            + self.measures[i_measure].get_event_offset(i_event)
+    }
+
+    pub fn get_ticks(&self) -> u32 {
+        self.measures.len() as u32 * self.time_signature.get_measure_ticks()
+    }
+
+    //x of the time in ticks from the start of the stave, for the cursor
+    pub fn get_ticks_x(&self, ticks: f32) -> i32 {
+        let gap_x = self.size.x / 18;
+        let measure_ticks = self.time_signature.get_measure_ticks() as f32;
+        let i_measure = (ticks / measure_ticks) as usize;
+        if i_measure >= self.measures.len() {
+            return self.get_measure_x(self.measures.len());
+        }
+        let offset = ticks - i_measure as f32 * measure_ticks;
+        self.get_measure_x(i_measure) + gap_x + (offset * (gap_x * 4) as f32 / measure_ticks) as i32
+    }
+
+    //events are placed according to their time in the measure
+    #[cfg(feature = "sdl")]
+    fn get_event_x(&self, i_measure: usize, i_event: usize) -> i32 {
+        let gap_x = self.size.x / 18;
+        let offset = self.measures[i_measure].get_event_offset(i_event) as i32;
+        self.get_measure_x(i_measure)
+            + gap_x
+            + offset * gap_x * 4 / self.time_signature.get_measure_ticks() as i32
+    }
+
+    #[cfg(feature = "sdl")]
+    pub fn draw(&self, y_pos: i32, canvas: &WindowCanvas) {
+        let pos = Point::new(self.x_pos, y_pos);
+        let small_gap_x = self.size.x / 60;
+
+        for clef in self.clefs.iter() {
+            let y_clef = pos.y + self.get_clef_y(clef);
+            //draw lines
+            for i in 0..5 {
+                canvas
+                    .thick_line(
+                        pos.x as i16,
+                        (y_clef + (self.gap * 2 * i) as i32) as i16,
+                        (pos.x + self.size.x as i32) as i16,
+                        (y_clef + self.gap * 2 * i) as i16,
+                        2,
+                        Color::BLACK,
+                    )
+                    .unwrap();
+            }
+
+            //draw clef
+            let pos_clef = match clef {
+                Clef::Sol => y_clef + self.size.y / 2,
+                Clef::Fa => y_clef + self.size.y / 2 - 20,
+            };
+            canvas
+                .string(
+                    pos.x as i16 - 23,
+                    pos_clef as i16,
+                    &clef.to_string(),
+                    Color::BLACK,
+                )
+                .unwrap();
+
+            //draw key_signature
+            let s;
+            let order;
+            match self.key_signature.0 {
+                KeySignatureAccidental::Sharp => {
+                    s = '#';
+                    order = ORDER_SIGNATURE_SHARP;
+                }
+                KeySignatureAccidental::Flat => {
+                    s = 'b';
+                    order = ORDER_SIGNATURE_FLAT;
+                }
+            }
+
+            for i in 0..self.key_signature.get_number() {
+                let y = y_clef + get_factor_gap_pitch(&order[i as usize], clef) * self.gap + 1
+                    - self.gap;
+                let x = pos.x + small_gap_x * i as i32;
+                canvas
+                    .character(x as i16, y as i16, s, Color::BLACK)
+                    .unwrap();
+            }
+
+            //draw time signature
+            if self.draw_time_signature {
+                let x = (pos.x + small_gap_x * 8) as i16;
+                canvas
+                    .string(
+                        x,
+                        (y_clef + self.gap * 2 - 4) as i16,
+                        &self.time_signature.0.to_string(),
+                        Color::BLACK,
+                    )
+                    .unwrap();
+                canvas
+                    .string(
+                        x,
+                        (y_clef + self.gap * 6 - 4) as i16,
+                        &self.time_signature.1.get_denominator().to_string(),
+                        Color::BLACK,
+                    )
+                    .unwrap();
+            }
+        }
+
+        //draw brace joining the staves
+        if self.clefs.len() > 1 {
+            let x = (pos.x - 30) as i16;
+            let top = pos.y as i16;
+            let bottom = (pos.y + self.get_height()) as i16;
+            let middle = (top + bottom) / 2;
+            canvas
+                .bezier(
+                    &[x + 6, x - 4, x + 4, x - 4],
+                    &[top, top + 10, middle - 10, middle],
+                    20,
+                    Color::BLACK,
+                )
+                .unwrap();
+            canvas
+                .bezier(
+                    &[x - 4, x + 4, x - 4, x + 6],
+                    &[middle, middle + 10, bottom - 10, bottom],
+                    20,
+                    Color::BLACK,
+                )
+                .unwrap();
+            canvas
+                .thick_line(pos.x as i16, top, pos.x as i16, bottom, 2, Color::BLACK)
+                .unwrap();
+        }
+
+        //draw measures
+        for (i_m, m) in self.measures.iter().enumerate() {
+            //draw measures separating lines
+            let x_measure = self.get_measure_x(i_m);
+            canvas
+                .thick_line(
+                    x_measure as i16,
+                    pos.y as i16,
+                    x_measure as i16,
+                    (pos.y + self.get_height()) as i16,
+                    2,
+                    Color::BLACK,
+                )
+                .unwrap();
+
+            //draw events
+            for (i_e, e) in m.events.iter().enumerate() {
+                let x = self.get_event_x(i_m, i_e);
+
+                if e.notes.is_empty() {
+                    for clef in self.clefs.iter() {
+                        self.draw_rest(x, pos.y + self.get_clef_y(clef), e.length, canvas);
+                    }
+                }
+
+                for (i_n, n) in e.notes.iter().enumerate() {
+                    let y_clef = pos.y + self.get_clef_y(&n.clef);
+                    let nb_factor_gap = get_factor_gap_pitch(&n.pitch, &n.clef)
+                        + get_factor_gap_octave(&n.octave, &n.clef);
+                    let y = y_clef + nb_factor_gap * self.gap;
+                    match e.length.value {
+                        //white notes
+                        NoteValue::Whole | NoteValue::Half => {
+                            canvas
+                                .circle(x as i16, y as i16, self.gap as i16, n.color)
+                                .unwrap();
+                            canvas
+                                .circle(x as i16, y as i16, (self.gap - 1) as i16, n.color)
+                                .unwrap();
+                        }
+                        _ => canvas
+                            .filled_circle(x as i16, y as i16, self.gap as i16, n.color)
+                            .unwrap(),
+                    }
+
+                    //draw dot, in the space above when the note is on a line
+                    if e.length.dotted {
+                        let y_dot = y - (nb_factor_gap + 1).rem_euclid(2) * self.gap;
+                        canvas
+                            .filled_circle((x + self.gap * 2) as i16, y_dot as i16, 2, n.color)
+                            .unwrap();
+                    }
+
+                    //draw tie from the previous event
+                    if e.tied {
+                        let x_previous = match i_e {
+                            0 => {
+                                let previous = &self.measures[i_m - 1];
+                                self.get_event_x(i_m - 1, previous.events.len() - 1)
+                            }
+                            _ => self.get_event_x(i_m, i_e - 1),
+                        };
+                        let y_tie = (y + self.gap + 2) as i16;
+                        canvas
+                            .bezier(
+                                &[
+                                    x_previous as i16 + 3,
+                                    x_previous as i16 + 8,
+                                    x as i16 - 8,
+                                    x as i16 - 3,
+                                ],
+                                &[y_tie, y_tie + 5, y_tie + 5, y_tie],
+                                10,
+                                n.color,
+                            )
+                            .unwrap();
+                    }
+
+                    //draw accidental
+                    if n.draw_acci && n.accidental.is_some() {
+                        //accidentals of a chord are shifted to not overlap
+                        let nb_acci = e.notes[..i_n]
+                            .iter()
+                            .filter(|o| o.clef == n.clef && o.draw_acci && o.accidental.is_some())
+                            .count() as i32;
+                        let x_acci = x - small_gap_x - 2 - nb_acci * 8;
+                        match n.accidental.unwrap() {
+                            Accidental::Sharp => canvas
+                                .character(x_acci as i16, (y - 4) as i16, '#', n.color)
+                                .unwrap(),
+                            Accidental::Flat => canvas
+                                .character(x_acci as i16, (y - 4) as i16, 'b', n.color)
+                                .unwrap(),
+                            Accidental::Natural => canvas
+                                .character(x_acci as i16, (y - 4) as i16, 'n', n.color)
+                                .unwrap(),
+                            Accidental::DoubleSharp => canvas
+                                .character(x_acci as i16, (y - 4) as i16, 'x', n.color)
+                                .unwrap(),
+                            Accidental::DoubleFlat => canvas
+                                .string((x_acci - 6) as i16, (y - 4) as i16, "bb", n.color)
+                                .unwrap(),
+                        }
+                    }
+
+                    //draw help lines
+                    let help_line_width = (self.gap as f32 * 1.5) as i32;
+                    if nb_factor_gap <= -2 {
+                        for i_y in (2..=-nb_factor_gap).step_by(2) {
+                            let y = y_clef + i_y * -self.gap;
+                            canvas
+                                .thick_line(
+                                    (x - help_line_width) as i16,
+                                    y as i16,
+                                    (x + help_line_width) as i16,
+                                    y as i16,
+                                    2,
+                                    n.color,
+                                )
+                                .unwrap();
+                        }
+                    } else if nb_factor_gap >= 10 {
+                        for i_y in (10..=nb_factor_gap).step_by(2) {
+                            let y = y_clef + i_y * self.gap;
+                            canvas
+                                .thick_line(
+                                    (x - help_line_width) as i16,
+                                    y as i16,
+                                    (x + help_line_width) as i16,
+                                    y as i16,
+                                    2,
+                                    n.color,
+                                )
+                                .unwrap();
+                        }
+                    }
+                }
+
+                //draw stems, one for each stave
+                if e.length.value != NoteValue::Whole {
+                    for clef in self.clefs.iter() {
+                        let ys: Vec<i32> = e
+                            .notes
+                            .iter()
+                            .filter(|n| n.clef == *clef)
+                            .map(|n| {
+                                get_factor_gap_pitch(&n.pitch, clef)
+                                    + get_factor_gap_octave(&n.octave, clef)
+                            })
+                            .collect();
+                        if let (Some(top), Some(bottom)) = (ys.iter().min(), ys.iter().max()) {
+                            let y_clef = pos.y + self.get_clef_y(clef);
+                            let color = e.notes[0].color;
+                            self.draw_stem(
+                                x,
+                                y_clef,
+                                (*top, *bottom),
+                                e.length.value,
+                                color,
+                                canvas,
+                            );
+                        }
+                    }
+                }
+
+                //draw pedal mark
+                if let Some(p) = e.pedal {
+                    let mark = match p {
+                        PedalMark::Press => "Ped.",
+                        PedalMark::Release => "*",
+                        PedalMark::Change => "*Ped.",
+                    };
+                    canvas
+                        .string(
+                            (x - self.gap) as i16,
+                            (pos.y + self.get_height() + self.gap * 8) as i16,
+                            mark,
+                            Color::BLACK,
+                        )
+                        .unwrap();
+                }
+            }
+        }
+    }
+
+    //top and bottom are the factor gaps of the highest and lowest notes
+    #[cfg(feature = "sdl")]
+    fn draw_stem(
+        &self,
+        x: i32,
+        y_clef: i32,
+        (top, bottom): (i32, i32),
+        value: NoteValue,
+        color: Color,
+        canvas: &WindowCanvas,
+    ) {
+        //notes under the middle line have their stem up
+        let up = top + bottom >= 8;
+        let (x_stem, y_start, y_end, dir) = match up {
+            true => (
+                x + self.gap - 1,
+                y_clef + bottom * self.gap,
+                y_clef + top * self.gap - self.gap * 7,
+                1,
+            ),
+            false => (
+                x - self.gap + 1,
+                y_clef + top * self.gap,
+                y_clef + bottom * self.gap + self.gap * 7,
+                -1,
+            ),
+        };
+        canvas
+            .thick_line(
+                x_stem as i16,
+                y_start as i16,
+                x_stem as i16,
+                y_end as i16,
+                2,
+                color,
+            )
+            .unwrap();
+
+        for i in 0..value.get_nb_flags() as i32 {
+            let y_flag = y_end + dir * i * self.gap * 3 / 2;
+            canvas
+                .thick_line(
+                    x_stem as i16,
+                    y_flag as i16,
+                    (x_stem + self.gap * 3 / 2) as i16,
+                    (y_flag + dir * self.gap * 5 / 2) as i16,
+                    2,
+                    color,
+                )
+                .unwrap();
+        }
+    }
+
+    #[cfg(feature = "sdl")]
+    fn draw_rest(&self, x: i32, y_clef: i32, length: NoteLength, canvas: &WindowCanvas) {
+        let g = self.gap;
+        match length.value {
+            //hangs under the 4th line
+            NoteValue::Whole => canvas
+                .box_(
+                    (x - g) as i16,
+                    (y_clef + g * 2) as i16,
+                    (x + g) as i16,
+                    (y_clef + g * 3) as i16,
+                    Color::BLACK,
+                )
+                .unwrap(),
+            //sits on the middle line
+            NoteValue::Half => canvas
+                .box_(
+                    (x - g) as i16,
+                    (y_clef + g * 3) as i16,
+                    (x + g) as i16,
+                    (y_clef + g * 4) as i16,
+                    Color::BLACK,
+                )
+                .unwrap(),
+            NoteValue::Quarter => {
+                let points = [
+                    (x - 2, y_clef + g),
+                    (x + 3, y_clef + g * 3),
+                    (x - 2, y_clef + g * 5),
+                    (x + 3, y_clef + g * 6),
+                    (x - 1, y_clef + g * 7),
+                ];
+                for p in points.windows(2) {
+                    canvas
+                        .thick_line(
+                            p[0].0 as i16,
+                            p[0].1 as i16,
+                            p[1].0 as i16,
+                            p[1].1 as i16,
+                            2,
+                            Color::BLACK,
+                        )
+                        .unwrap();
+                }
+            }
+            NoteValue::Eighth | NoteValue::Sixteenth => {
+                let nb_flags = length.value.get_nb_flags() as i32;
+                canvas
+                    .thick_line(
+                        (x + 3) as i16,
+                        (y_clef + g * 3) as i16,
+                        (x - 1) as i16,
+                        (y_clef + g * (5 + nb_flags * 2)) as i16,
+                        2,
+                        Color::BLACK,
+                    )
+                    .unwrap();
+                for i in 0..nb_flags {
+                    let y = y_clef + g * 3 + i * g * 2;
+                    canvas
+                        .filled_circle((x - 2) as i16, y as i16, 2, Color::BLACK)
+                        .unwrap();
+                    canvas
+                        .thick_line(
+                            (x - 2) as i16,
+                            y as i16,
+                            (x + 3 - i) as i16,
+                            y as i16,
+                            1,
+                            Color::BLACK,
+                        )
+                        .unwrap();
+                }
+            }
+        }
+
+        if length.dotted {
+            canvas
+                .filled_circle((x + g * 2) as i16, (y_clef + g * 3) as i16, 2, Color::BLACK)
+                .unwrap();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn measure_writes_the_sounding_accidentals() {
+        let key_signature = KeySignature::new(KeySignatureAccidental::Sharp, 1);
+        let quarter = NoteLength::new(NoteValue::Quarter, false);
+        let f = Note::new(Pitch::F, None, Octave(3), Clef::Sol);
+        let c = Note::new(Pitch::C, None, Octave(3), Clef::Sol);
+        let events = [f, c, f, c]
+            .iter()
+            .map(|n| MeasureEvent::new(vec![*n], quarter))
+            .collect();
+        let m = Measure::new(events, key_signature, TimeSignature(4, NoteValue::Quarter)).unwrap();
+        assert_eq!(m.events[0].notes[0].accidental, Some(Accidental::Sharp));
+        assert_eq!(m.events[0].notes[0].accidental, f.get_sounding(key_signature).accidental);
+        assert_eq!(m.events[1].notes[0].accidental, c.get_sounding(key_signature).accidental);
+    }
+
+    #[test]
+    fn notes_out_of_the_midi_notes() {
+        let far = Note::new(Pitch::C, None, Octave(20), Clef::Sol);
+        let c = Note::new(Pitch::C, None, Octave(3), Clef::Sol);
+        let b_sharp = Note::new(Pitch::B, Some(Accidental::Sharp), Octave(2), Clef::Fa);
+        assert!(far != c);
+        assert!(far == far);
+        assert!(c == b_sharp);
+        let quarter = NoteLength::new(NoteValue::Quarter, false);
+        let e = MeasureEvent::new(vec![far, c, b_sharp], quarter);
+        assert_eq!(e.get_semitones(), vec![Semitone(60)]);
+        assert_eq!(Interval::between(&far, &c), None);
+    }
+}